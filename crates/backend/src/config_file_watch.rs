@@ -1,25 +1,36 @@
-use std::{sync::Arc, time::Duration};
+use std::{
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
 
 use calloop::channel::Sender;
-use config::get_config_path;
+use config::get_config_files;
 use futures_util::StreamExt;
 use inotify::{EventMask, Inotify, WatchMask};
 use log::info;
+use tokio::sync::Notify;
 
 use crate::runtime::get_backend_runtime_handle;
 
+fn watch_list_changed() -> &'static Notify {
+    static NOTIFY: OnceLock<Notify> = OnceLock::new();
+    NOTIFY.get_or_init(Notify::new)
+}
+
+/// call after the config is loaded, included files may have changed.
+pub fn refresh_watched_files() {
+    watch_list_changed().notify_one();
+}
+
 pub fn start_configuration_file_watcher(sender: Sender<()>) {
     get_backend_runtime_handle().spawn(async move {
         let mut debouncer = None;
 
         loop {
             let inotify = Inotify::init().unwrap();
-            let file_path = get_config_path();
-
-            inotify
-                .watches()
-                .add(
-                    file_path,
+            for file_path in get_config_files() {
+                if let Err(e) = inotify.watches().add(
+                    &file_path,
                     WatchMask::CREATE
                         | WatchMask::MODIFY
                         | WatchMask::DELETE
@@ -27,12 +38,21 @@ pub fn start_configuration_file_watcher(sender: Sender<()>) {
                         | WatchMask::MOVE
                         | WatchMask::MOVE_SELF
                         | WatchMask::ATTRIB,
-                )
-                .unwrap();
+                ) {
+                    log::warn!("Failed to watch config file {}: {e}", file_path.display());
+                }
+            }
 
             let mut buffer = [0; 1024];
             let mut stream = inotify.into_event_stream(&mut buffer).unwrap();
-            while let Some(event_or_error) = stream.next().await {
+            loop {
+                let event_or_error = tokio::select! {
+                    e = stream.next() => e,
+                    _ = watch_list_changed().notified() => break,
+                };
+                let Some(event_or_error) = event_or_error else {
+                    break;
+                };
                 let event = event_or_error.unwrap();
                 info!("Received inotify event: {event:?}");
 
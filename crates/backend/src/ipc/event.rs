@@ -0,0 +1,55 @@
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PopDirection {
+    Forward,
+    Backward,
+}
+
+/// state changes streamed to `subscribe` clients, one json object per line.
+/// `pin` and `pop` come from the widget of each output, `output` tells them apart.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum IPCEvent {
+    Pin {
+        namespace: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        output: Option<String>,
+        pinned: bool,
+    },
+    Pop {
+        namespace: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        output: Option<String>,
+        direction: PopDirection,
+    },
+    ReloadStart,
+    ReloadFinish {
+        widgets: usize,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    SliderValue {
+        namespace: String,
+        value: f64,
+    },
+}
+
+static EVENT_CHANNEL: OnceLock<broadcast::Sender<IPCEvent>> = OnceLock::new();
+
+fn get_event_channel() -> &'static broadcast::Sender<IPCEvent> {
+    EVENT_CHANNEL.get_or_init(|| broadcast::channel(64).0)
+}
+
+pub fn emit_event(event: IPCEvent) {
+    // no subscribers is not an error
+    let _ = get_event_channel().send(event);
+}
+
+pub(super) fn subscribe_events() -> broadcast::Receiver<IPCEvent> {
+    get_event_channel().subscribe()
+}
@@ -1,14 +1,25 @@
 use crate::ipc::{get_ipc_sock, IPC_COMMAND_RELOAD};
 use crate::runtime::get_backend_runtime_handle;
 
-use super::{CommandBody, IPCCommand};
-use super::{IPC_COMMAND_QUIT, IPC_COMMAND_TOGGLE_PIN};
+use super::event::subscribe_events;
+use super::{CommandBody, IPCCommand, IPCResponder, IPCResponse, IPC_PROTOCOL_VERSION};
+use super::{
+    DEFAULT_POP_DURATION, IPC_COMMAND_DISABLE, IPC_COMMAND_ENABLE, IPC_COMMAND_HIDE,
+    IPC_COMMAND_LIST, IPC_COMMAND_POP, IPC_COMMAND_PUSH, IPC_COMMAND_QUIT, IPC_COMMAND_SET,
+    IPC_COMMAND_SHOW, IPC_COMMAND_SUBSCRIBE, IPC_COMMAND_TOGGLE_PIN, IPC_COMMAND_TRIGGER,
+};
 use std::path::Path;
 
 use calloop::channel::Sender;
-use tokio::net::UnixStream;
+use config::def::shared::parse_key_binding;
+use tokio::{
+    net::UnixStream,
+    sync::{broadcast::error::RecvError, oneshot},
+};
 
-pub fn start_ipc(sender: Sender<IPCCommand>) {
+pub type IPCSender = Sender<(IPCCommand, IPCResponder)>;
+
+pub fn start_ipc(sender: IPCSender) {
     get_backend_runtime_handle().spawn(async {
         let listener = {
             let path = Path::new(get_ipc_sock());
@@ -33,55 +44,232 @@ pub fn start_ipc(sender: Sender<IPCCommand>) {
     });
 }
 
-fn deal_stream_in_background(stream: UnixStream, sender: Sender<IPCCommand>) {
+fn deal_stream_in_background(stream: UnixStream, sender: IPCSender) {
     tokio::spawn(async move {
-        let raw = stream_read_all(&stream).await?;
-        log::debug!("recv ipc msg: {raw}");
-        let command_body =
-            serde_jsonrc::from_str::<CommandBody>(&raw).map_err(|e| e.to_string())?;
-        let ipc = match command_body.command.as_str() {
-            IPC_COMMAND_TOGGLE_PIN => {
-                IPCCommand::TogglePin(command_body.args.first().ok_or("No widget name")?.clone())
-            }
-            IPC_COMMAND_QUIT => IPCCommand::Exit,
-            IPC_COMMAND_RELOAD => IPCCommand::Reload,
-            _ => return Err("unknown command".to_string()),
-        };
-        log::info!("Receive ipc message: {ipc:?}");
-        sender
-            .send(ipc)
-            .map_err(|_| "ipc channel closed".to_string())?;
-        Ok(())
+        let mut reader = LineReader::default();
+        loop {
+            let line = match reader.next_line(&stream).await {
+                Ok(Some(line)) => line,
+                Ok(None) => break,
+                Err(e) => {
+                    log::error!("{e}");
+                    break;
+                }
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let body = parse_body(&line);
+            let is_subscribe = matches!(&body, Ok(body) if body.command == IPC_COMMAND_SUBSCRIBE);
+            let res = match body {
+                Ok(_) if is_subscribe => IPCResponse::ok().with_message("subscribed"),
+                Ok(body) => handle_command(body, &sender).await,
+                Err(e) => IPCResponse::error(e),
+            };
+            if !res.is_ok() {
+                log::error!("IPC command failed: {:?}", res.message);
+            }
+
+            let mut data = serde_jsonrc::to_string(&res).unwrap();
+            data.push('\n');
+            if let Err(e) = stream_write_all(&stream, data.as_bytes()).await {
+                log::error!("Fail to reply ipc command: {e}");
+                break;
+            }
+
+            // the connection is dedicated to events from now on
+            if is_subscribe {
+                stream_events(&stream).await;
+                break;
+            }
+        }
     });
 }
 
-async fn stream_read_all(stream: &UnixStream) -> Result<String, String> {
-    let mut buf_array = vec![];
-    let a = loop {
-        // Wait for the socket to be readable
-        if stream.readable().await.is_err() {
-            return Err("stream not readable".to_string());
+fn parse_body(line: &str) -> Result<CommandBody, String> {
+    log::debug!("recv ipc msg: {line}");
+    let body =
+        serde_jsonrc::from_str::<CommandBody>(line).map_err(|e| format!("Invalid command: {e}"))?;
+    if body.version != IPC_PROTOCOL_VERSION {
+        return Err(format!(
+            "Incompatible ipc protocol version {}, daemon speaks version {IPC_PROTOCOL_VERSION}, please use the way-edges of the same version as the daemon",
+            body.version
+        ));
+    }
+    Ok(body)
+}
+
+async fn stream_events(stream: &UnixStream) {
+    log::info!("New ipc event subscriber");
+    let mut receiver = subscribe_events();
+    loop {
+        let event = match receiver.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(n)) => {
+                log::warn!("ipc subscriber lagged behind, {n} events skipped");
+                continue;
+            }
+            Err(RecvError::Closed) => break,
+        };
+
+        let mut line = serde_jsonrc::to_string(&event).unwrap();
+        line.push('\n');
+        // client closed the connection
+        if stream_write_all(stream, line.as_bytes()).await.is_err() {
+            break;
         }
+    }
+    log::info!("ipc event subscriber left");
+}
 
-        // Creating the buffer **after** the `await` prevents it from
-        // being stored in the async task.
-        let mut buf = [0; 4096];
+async fn handle_command(command_body: CommandBody, sender: &IPCSender) -> IPCResponse {
+    let ipc = match parse_command(command_body) {
+        Ok(ipc) => ipc,
+        Err(e) => return IPCResponse::error(e),
+    };
+    log::info!("Receive ipc message: {ipc:?}");
 
-        // Try to read data, this may still fail with `WouldBlock`
-        // if the readiness event is a false positive.
-        match stream.try_read(&mut buf) {
-            Ok(0) => break String::from_utf8_lossy(&buf_array),
-            Ok(n) => {
-                buf_array.extend_from_slice(&buf[..n]);
+    let (tx, rx) = oneshot::channel();
+    if sender.send((ipc, IPCResponder(tx))).is_err() {
+        return IPCResponse::error("ipc channel closed");
+    }
+    rx.await
+        .unwrap_or_else(|_| IPCResponse::error("daemon dropped the command without reply"))
+}
+
+fn parse_command(command_body: CommandBody) -> Result<IPCCommand, String> {
+    let widget_name = || {
+        command_body
+            .args
+            .first()
+            .cloned()
+            .ok_or("No widget name".to_string())
+    };
+    let ipc = match command_body.command.as_str() {
+        IPC_COMMAND_TOGGLE_PIN => IPCCommand::TogglePin(widget_name()?),
+        IPC_COMMAND_SHOW => IPCCommand::Show(widget_name()?),
+        IPC_COMMAND_HIDE => IPCCommand::Hide(widget_name()?),
+        IPC_COMMAND_DISABLE => IPCCommand::Disable(widget_name()?),
+        IPC_COMMAND_ENABLE => IPCCommand::Enable(widget_name()?),
+        IPC_COMMAND_POP => {
+            let duration = match command_body.args.get(1) {
+                Some(d) => d
+                    .parse()
+                    .map_err(|e| format!("Invalid pop duration `{d}`: {e}"))?,
+                None => DEFAULT_POP_DURATION,
+            };
+            IPCCommand::Pop {
+                namespace: widget_name()?,
+                duration,
             }
+        }
+        IPC_COMMAND_LIST => IPCCommand::List,
+        IPC_COMMAND_PUSH => {
+            let target = widget_name()?;
+            let (namespace, item) = target.rsplit_once('/').ok_or(format!(
+                "Invalid target `{target}`, format: <namespace>/<item-id>"
+            ))?;
+            IPCCommand::Push {
+                namespace: namespace.to_string(),
+                item: item.to_string(),
+                value: command_body.args.get(1).ok_or("No value")?.clone(),
+                label: command_body.args.get(2).cloned(),
+            }
+        }
+        IPC_COMMAND_SET => {
+            let value = command_body.args.get(1).ok_or("No value")?;
+            let value = value
+                .parse()
+                .map_err(|e| format!("Invalid value `{value}`: {e}"))?;
+            IPCCommand::Set {
+                namespace: widget_name()?,
+                value,
+            }
+        }
+        IPC_COMMAND_TRIGGER => {
+            let target = widget_name()?;
+            let (namespace, item) = match target.rsplit_once('/') {
+                Some((namespace, item)) => (namespace.to_string(), Some(item.to_string())),
+                None => (target, None),
+            };
+            IPCCommand::Trigger {
+                namespace,
+                item,
+                key: parse_key_binding(command_body.args.get(1).ok_or("No key")?)?,
+            }
+        }
+        IPC_COMMAND_QUIT => IPCCommand::Exit,
+        IPC_COMMAND_RELOAD => IPCCommand::Reload,
+        cmd => return Err(format!("Unknown command: {cmd}")),
+    };
+    Ok(ipc)
+}
+
+/// splits what the client sends into lines, the last line does not need a trailing newline.
+#[derive(Default)]
+struct LineReader {
+    buf: Vec<u8>,
+    eof: bool,
+}
+impl LineReader {
+    async fn next_line(&mut self, stream: &UnixStream) -> Result<Option<String>, String> {
+        loop {
+            if let Some(pos) = self.buf.iter().position(|b| *b == b'\n') {
+                let line = self.buf.drain(..=pos).collect::<Vec<_>>();
+                return Ok(Some(String::from_utf8_lossy(&line).to_string()));
+            }
+            if self.eof {
+                if self.buf.is_empty() {
+                    return Ok(None);
+                }
+                let line = std::mem::take(&mut self.buf);
+                return Ok(Some(String::from_utf8_lossy(&line).to_string()));
+            }
+
+            // Wait for the socket to be readable
+            if stream.readable().await.is_err() {
+                return Err("stream not readable".to_string());
+            }
+
+            // Creating the buffer **after** the `await` prevents it from
+            // being stored in the async task.
+            let mut buf = [0; 4096];
+
+            // Try to read data, this may still fail with `WouldBlock`
+            // if the readiness event is a false positive.
+            match stream.try_read(&mut buf) {
+                Ok(0) => self.eof = true,
+                Ok(n) => {
+                    self.buf.extend_from_slice(&buf[..n]);
+                }
+                Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    continue;
+                }
+                Err(e) => {
+                    return Err(format!("Can not read command: {e}"));
+                }
+            }
+        }
+    }
+}
+
+async fn stream_write_all(stream: &UnixStream, mut data: &[u8]) -> Result<(), String> {
+    while !data.is_empty() {
+        if stream.writable().await.is_err() {
+            return Err("stream not writable".to_string());
+        }
+
+        match stream.try_write(data) {
+            Ok(n) => data = &data[n..],
             Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
                 continue;
             }
             Err(e) => {
-                return Err(format!("Can not read command: {e}"));
+                return Err(format!("Can not write reply: {e}"));
             }
         }
-    };
+    }
 
-    Ok(a.to_string())
+    Ok(())
 }
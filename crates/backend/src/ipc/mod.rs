@@ -1,26 +1,60 @@
+mod event;
 mod listen;
+mod pattern;
 use std::{
-    io::Write,
+    io::{BufRead, BufReader, Write},
     os::unix::net::UnixStream,
     path::{Path, PathBuf},
     sync::OnceLock,
 };
 
-pub use listen::start_ipc;
+pub use event::{emit_event, IPCEvent, PopDirection};
+pub use listen::{start_ipc, IPCSender};
+pub use pattern::NamespacePattern;
 
+use config::def::shared::KeyBinding;
 use serde::{Deserialize, Serialize};
+use serde_jsonrc::Value;
+use tokio::sync::oneshot;
 
+/// bump when the meaning of commands or replies changes incompatibly.
+pub const IPC_PROTOCOL_VERSION: u32 = 1;
+
+/// one line of json on the socket, a connection can carry many of them.
 #[derive(Debug, Deserialize, Serialize)]
 pub struct CommandBody {
+    #[serde(default)]
+    pub version: u32,
     #[serde(default)]
     pub command: String,
     #[serde(default)]
     pub args: Vec<String>,
 }
+impl CommandBody {
+    pub fn new(command: impl Into<String>, args: Vec<String>) -> Self {
+        Self {
+            version: IPC_PROTOCOL_VERSION,
+            command: command.into(),
+            args,
+        }
+    }
+}
 
 pub const IPC_COMMAND_RELOAD: &str = "reload";
 pub const IPC_COMMAND_QUIT: &str = "q";
 pub const IPC_COMMAND_TOGGLE_PIN: &str = "togglepin";
+pub const IPC_COMMAND_SHOW: &str = "show";
+pub const IPC_COMMAND_HIDE: &str = "hide";
+pub const IPC_COMMAND_POP: &str = "pop";
+pub const IPC_COMMAND_LIST: &str = "list";
+pub const IPC_COMMAND_SUBSCRIBE: &str = "subscribe";
+pub const IPC_COMMAND_SET: &str = "set";
+pub const IPC_COMMAND_PUSH: &str = "push";
+pub const IPC_COMMAND_TRIGGER: &str = "trigger";
+pub const IPC_COMMAND_DISABLE: &str = "disable";
+pub const IPC_COMMAND_ENABLE: &str = "enable";
+
+pub const DEFAULT_POP_DURATION: u64 = 1000;
 
 static SOCK_FILE: OnceLock<PathBuf> = OnceLock::new();
 
@@ -38,15 +72,180 @@ fn get_ipc_sock() -> &'static Path {
     SOCK_FILE.get().expect("IPC socket file not set")
 }
 
-pub fn send_command(cmd: CommandBody) {
-    let data = serde_jsonrc::to_string(&cmd).unwrap();
-    let mut socket = UnixStream::connect(get_ipc_sock()).unwrap();
-    socket.write_all(data.as_bytes()).unwrap();
+pub struct IPCClient {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+}
+impl IPCClient {
+    pub fn connect() -> Result<Self, String> {
+        let path = get_ipc_sock();
+        let writer = UnixStream::connect(path).map_err(|e| {
+            format!(
+                "Can not connect to way-edges daemon at {}: {e}, is it running?",
+                path.display()
+            )
+        })?;
+        let reader = writer
+            .try_clone()
+            .map(BufReader::new)
+            .map_err(|e| e.to_string())?;
+        Ok(Self { reader, writer })
+    }
+
+    fn read_line(&mut self) -> Result<String, String> {
+        let mut line = String::new();
+        match self.reader.read_line(&mut line) {
+            Ok(0) => Err("Daemon closed the connection".to_string()),
+            Ok(_) => Ok(line),
+            Err(e) => Err(format!("Fail to read reply: {e}")),
+        }
+    }
+
+    /// send one command and wait for its reply, can be called many times on one connection.
+    pub fn send(&mut self, cmd: CommandBody) -> Result<IPCResponse, String> {
+        let mut data = serde_jsonrc::to_string(&cmd).map_err(|e| e.to_string())?;
+        data.push('\n');
+        self.writer
+            .write_all(data.as_bytes())
+            .map_err(|e| format!("Fail to send command: {e}"))?;
+
+        let raw = self.read_line()?;
+        let res: IPCResponse = serde_jsonrc::from_str(&raw)
+            .map_err(|e| format!("Invalid reply from daemon: {e}: {raw}"))?;
+        if res.is_ok() && res.version != IPC_PROTOCOL_VERSION {
+            return Err(format!(
+                "Daemon speaks ipc protocol version {}, but this client speaks {IPC_PROTOCOL_VERSION}",
+                res.version
+            ));
+        }
+        Ok(res)
+    }
+
+    /// call `f` with every event line until the daemon closes the connection.
+    pub fn subscribe(mut self, mut f: impl FnMut(&str)) -> Result<(), String> {
+        let res = self.send(CommandBody::new(IPC_COMMAND_SUBSCRIBE, vec![]))?;
+        if !res.is_ok() {
+            return Err(res.message.unwrap_or_default());
+        }
+
+        for line in self.reader.lines() {
+            let line = line.map_err(|e| format!("Fail to read event: {e}"))?;
+            f(&line);
+        }
+        Ok(())
+    }
+}
+
+pub fn send_command(cmd: CommandBody) -> Result<IPCResponse, String> {
+    IPCClient::connect()?.send(cmd)
+}
+
+pub fn subscribe(f: impl FnMut(&str)) -> Result<(), String> {
+    IPCClient::connect()?.subscribe(f)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IPCStatus {
+    Ok,
+    Error,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct IPCResponse {
+    #[serde(default)]
+    pub version: u32,
+    pub status: IPCStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payload: Option<Value>,
+}
+impl IPCResponse {
+    pub fn ok() -> Self {
+        Self {
+            version: IPC_PROTOCOL_VERSION,
+            status: IPCStatus::Ok,
+            message: None,
+            payload: None,
+        }
+    }
+    pub fn error(msg: impl Into<String>) -> Self {
+        Self {
+            version: IPC_PROTOCOL_VERSION,
+            status: IPCStatus::Error,
+            message: Some(msg.into()),
+            payload: None,
+        }
+    }
+    pub fn with_message(mut self, msg: impl Into<String>) -> Self {
+        self.message = Some(msg.into());
+        self
+    }
+    pub fn with_payload(mut self, payload: impl Serialize) -> Self {
+        self.payload = serde_jsonrc::to_value(payload).ok();
+        self
+    }
+    pub fn is_ok(&self) -> bool {
+        self.status == IPCStatus::Ok
+    }
+}
+impl From<Result<IPCResponse, String>> for IPCResponse {
+    fn from(value: Result<IPCResponse, String>) -> Self {
+        value.unwrap_or_else(Self::error)
+    }
+}
+
+/// state of one running widget, payload of `list`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WidgetStatus {
+    pub namespace: String,
+    #[serde(rename = "type")]
+    pub widget_type: String,
+    pub output: Option<String>,
+    pub edge: String,
+    pub pinned: bool,
+    pub pop_progress: f64,
+    pub configured: bool,
 }
 
 #[derive(Debug)]
 pub enum IPCCommand {
     TogglePin(String),
+    Show(String),
+    Hide(String),
+    Pop {
+        namespace: String,
+        duration: u64,
+    },
+    List,
+    Set {
+        namespace: String,
+        value: f64,
+    },
+    Push {
+        namespace: String,
+        item: String,
+        value: String,
+        label: Option<String>,
+    },
+    Trigger {
+        namespace: String,
+        item: Option<String>,
+        key: KeyBinding,
+    },
+    Disable(String),
+    Enable(String),
     Reload,
     Exit,
 }
+
+/// every received command comes with one of this, the reply is written back to the client.
+#[derive(Debug)]
+pub struct IPCResponder(oneshot::Sender<IPCResponse>);
+impl IPCResponder {
+    pub fn reply(self, res: impl Into<IPCResponse>) {
+        // client may have gone away, nothing to do then
+        let _ = self.0.send(res.into());
+    }
+}
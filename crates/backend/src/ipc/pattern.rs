@@ -0,0 +1,85 @@
+use regex_lite::Regex;
+
+/// target of ipc commands, namespaces are grouped with `:`, e.g. `audio:speaker`.
+///
+/// - `audio` matches `audio` and everything under the group, like `audio:speaker`
+/// - `*` matches anything but `:`, `**` matches anything, `?` matches one char but `:`
+/// - `re:<regex>` matches with regex
+#[derive(Debug, Clone)]
+pub enum NamespacePattern {
+    Group(String),
+    Regex(Regex),
+}
+impl NamespacePattern {
+    pub fn parse(pattern: &str) -> Result<Self, String> {
+        if let Some(re) = pattern.strip_prefix("re:") {
+            return Regex::new(re)
+                .map(Self::Regex)
+                .map_err(|e| format!("Invalid regex `{re}`: {e}"));
+        }
+
+        if !pattern.contains(['*', '?']) {
+            return Ok(Self::Group(pattern.to_string()));
+        }
+
+        let mut re = String::from("^");
+        let mut chars = pattern.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '*' if chars.next_if_eq(&'*').is_some() => re.push_str(".*"),
+                '*' => re.push_str("[^:]*"),
+                '?' => re.push_str("[^:]"),
+                c => re.push_str(&regex_lite::escape(c.encode_utf8(&mut [0; 4]))),
+            }
+        }
+        re.push('$');
+        Regex::new(&re)
+            .map(Self::Regex)
+            .map_err(|e| format!("Invalid pattern `{pattern}`: {e}"))
+    }
+
+    pub fn matches(&self, namespace: &str) -> bool {
+        match self {
+            Self::Group(group) => namespace
+                .strip_prefix(group.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with(':')),
+            Self::Regex(re) => re.is_match(namespace),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, namespace: &str) -> bool {
+        NamespacePattern::parse(pattern).unwrap().matches(namespace)
+    }
+
+    #[test]
+    fn test_group() {
+        assert!(matches("audio", "audio"));
+        assert!(matches("audio", "audio:speaker"));
+        assert!(matches("audio:speaker", "audio:speaker"));
+        assert!(!matches("audio", "audio-mic"));
+        assert!(!matches("audio:speaker", "audio"));
+    }
+
+    #[test]
+    fn test_glob() {
+        assert!(matches("audio:*", "audio:speaker"));
+        assert!(!matches("audio:*", "audio:speaker:left"));
+        assert!(matches("audio:**", "audio:speaker:left"));
+        assert!(matches("*:left", "workspace:left"));
+        assert!(!matches("*:left", "workspace:right"));
+        assert!(matches("mon-?", "mon-1"));
+        assert!(!matches("mon.?", "mon-1"));
+    }
+
+    #[test]
+    fn test_regex() {
+        assert!(matches(r"re:^mon-\d$", "mon-1"));
+        assert!(!matches(r"re:^mon-\d$", "mon-10"));
+        assert!(NamespacePattern::parse("re:(").is_err());
+    }
+}
@@ -100,7 +100,12 @@ impl PA {
         key
     }
     fn remove_cb(&mut self, key: i32) {
-        self.cbs.remove_entry(&key);
+        if self.cbs.remove(&key).is_some() {
+            self.device_map.retain(|_, ids| {
+                ids.remove(&key);
+                !ids.is_empty()
+            });
+        }
     }
 }
 
@@ -139,3 +144,26 @@ pub fn unregister_callback(key: i32) {
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_call_after_remove_cb() {
+        let device = PulseAudioDevice::NamedSink("speaker".to_string());
+        let mut pa = PA::new();
+        let (tx, _rx) = calloop::channel::channel();
+        let removed = pa.add_cb(tx, device.clone());
+        let (tx, _rx_kept) = calloop::channel::channel();
+        let kept = pa.add_cb(tx, device.clone());
+
+        pa.remove_cb(removed);
+        assert_eq!(pa.device_map[&device], HashSet::from([kept]));
+        pa.call(device.clone(), VInfo::default());
+
+        pa.remove_cb(kept);
+        assert!(pa.device_map.is_empty());
+        pa.call(device, VInfo::default());
+    }
+}
@@ -79,7 +79,7 @@ fn get_focus() -> i32 {
     hyprland::data::Workspace::get_active().unwrap().id
 }
 
-fn get_focused_monitor() -> Option<String> {
+pub(super) fn get_focused_monitor() -> Option<String> {
     hyprland::data::Monitors::get()
         .ok()?
         .into_iter()
@@ -87,6 +87,24 @@ fn get_focused_monitor() -> Option<String> {
         .map(|m| m.name)
 }
 
+// counted from the first workspace of the focused monitor, as the widget does
+pub(super) fn focus_workspace(n: i32) -> Result<(), String> {
+    use hyprland::dispatch::*;
+    let monitor = get_focused_monitor().ok_or("No focused hyprland monitor")?;
+    let first = hyprland::data::Workspaces::get()
+        .map_err(|e| format!("Failed to get hyprland workspaces: {e}"))?
+        .into_iter()
+        .filter(|w| w.monitor == monitor && w.id > 0)
+        .map(|w| w.id)
+        .min()
+        .ok_or_else(|| format!("No workspace on hyprland monitor {monitor}"))?;
+    let id = first + n - 1;
+    Dispatch::call(DispatchType::Workspace(WorkspaceIdentifierWithSpecial::Id(
+        id,
+    )))
+    .map_err(|e| format!("Failed to focus hyprland workspace {id}: {e}"))
+}
+
 fn on_signal() {
     let ctx = get_hypr_ctx();
     let is_initial_sync = ctx.data.is_default();
@@ -7,6 +7,37 @@ use niri::NiriWorkspaceHandler;
 pub mod hypr;
 pub mod niri;
 
+/// `hyprland` or `niri`, told by the env vars their ipc sockets are found with.
+pub fn running_compositor() -> Option<&'static str> {
+    if std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some() {
+        Some("hyprland")
+    } else if std::env::var_os(::niri_ipc::socket::SOCKET_PATH_ENV).is_some() {
+        Some("niri")
+    } else {
+        None
+    }
+}
+
+/// name of the output the compositor focuses, hyprland and niri only.
+pub fn focused_output() -> Option<String> {
+    match running_compositor()? {
+        "hyprland" => hypr::get_focused_monitor(),
+        "niri" => niri::get_focused_output(),
+        _ => None,
+    }
+}
+
+/// focus workspace `n` (from 1) of the focused output, hyprland and niri only.
+pub fn focus_workspace(n: u32) -> Result<(), String> {
+    match running_compositor() {
+        Some("hyprland") => hypr::focus_workspace(n as i32),
+        Some("niri") => niri::focus_workspace(
+            u8::try_from(n).map_err(|_| format!("No such niri workspace: {n}"))?,
+        ),
+        _ => Err("switching workspaces needs hyprland or niri".to_string()),
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct WorkspaceData {
     /// workspace len, start from 1
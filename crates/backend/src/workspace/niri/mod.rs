@@ -140,6 +140,31 @@ async fn get_workspaces() -> io::Result<Vec<niri_ipc::Workspace>> {
     }
 }
 
+pub(super) fn get_focused_output() -> Option<String> {
+    let mut socket = niri_ipc::socket::Socket::connect().ok()?;
+    match socket.send(niri_ipc::Request::FocusedOutput) {
+        Ok(Ok(niri_ipc::Response::FocusedOutput(output))) => output.map(|o| o.name),
+        res => {
+            log::warn!("Failed to get focused output from niri: {res:?}");
+            None
+        }
+    }
+}
+
+pub(super) fn focus_workspace(index: u8) -> Result<(), String> {
+    let mut socket = niri_ipc::socket::Socket::connect()
+        .map_err(|e| format!("Failed to connect to niri socket: {e}"))?;
+    match socket.send(niri_ipc::Request::Action(
+        niri_ipc::Action::FocusWorkspace {
+            reference: niri_ipc::WorkspaceReferenceArg::Index(index),
+        },
+    )) {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(e)) => Err(format!("Failed to focus niri workspace {index}: {e}")),
+        Err(e) => Err(format!("Failed to focus niri workspace {index}: {e}")),
+    }
+}
+
 static CTX_INITED: AtomicBool = AtomicBool::new(false);
 static GLOBAL_NIRI_LISTENER_CTX: AtomicPtr<NiriCtx> = AtomicPtr::new(std::ptr::null_mut());
 fn is_ctx_inited() -> bool {
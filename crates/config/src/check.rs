@@ -0,0 +1,157 @@
+use std::{collections::HashMap, path::Path};
+
+use crate::{
+    def::{shared::CommonSize, widgets::wrapbox::BoxConfig, Root, WidgetConf},
+    load::Loader,
+};
+
+/// load the config and every included file, then validate it.
+/// unlike `get_config`, all problems found are returned instead of the first one.
+/// `strict` makes unknown nodes errors, like `strict` in the config does.
+pub fn check_config(path: &Path, strict: bool) -> Result<Root, Vec<String>> {
+    let mut loader = Loader {
+        keep_going: true,
+        strict,
+        ..Default::default()
+    };
+    let res = loader.load(path);
+
+    let mut errors = loader.errors;
+    match res {
+        Ok(root) => {
+            errors.extend(validate(&root));
+            if errors.is_empty() {
+                Ok(root)
+            } else {
+                Err(errors)
+            }
+        }
+        Err(e) => {
+            errors.insert(0, e);
+            Err(errors)
+        }
+    }
+}
+
+/// what can not be told while decoding a single field.
+pub fn validate(root: &Root) -> Vec<String> {
+    let mut errors = vec![];
+    for (i, w) in root.widgets.iter().enumerate() {
+        let name = match w.common().namespace.as_str() {
+            "" => format!("{} #{i}", w.type_name()),
+            ns => format!("{} `{ns}`", w.type_name()),
+        };
+        let mut problems = vec![];
+
+        if !w.common().extra_trigger_size.is_valid_length()
+            && !w.common().extra_trigger_size.is_zero()
+        {
+            problems.push("extra-trigger-size can not be negative".to_string());
+        }
+        match w {
+            WidgetConf::Btn(c) => validate_size(&c.widget.size, &mut problems),
+            WidgetConf::Slider(c) => validate_size(&c.widget.size, &mut problems),
+            WidgetConf::Workspace(c) => validate_size(&c.widget.size, &mut problems),
+            WidgetConf::WrapBox(c) => validate_box(&c.widget, &mut problems),
+        }
+
+        errors.extend(problems.into_iter().map(|p| format!("{name}: {p}")));
+    }
+    errors
+}
+
+fn validate_size(size: &CommonSize, problems: &mut Vec<String>) {
+    if !size.thickness.is_valid_length() {
+        problems.push("thickness must be greater than 0".to_string());
+    }
+    if !size.length.is_valid_length() {
+        problems.push("length must be greater than 0".to_string());
+    }
+}
+
+fn validate_box(conf: &BoxConfig, problems: &mut Vec<String>) {
+    let mut positions = HashMap::new();
+    let mut ids = HashMap::new();
+    for (i, item) in conf.items.iter().enumerate() {
+        let [row, col] = item.index;
+        if row < -1 || col < -1 {
+            problems.push(format!(
+                "item #{i}: index {row} {col}, must be positive or -1"
+            ));
+        } else if row >= 0 && col >= 0 {
+            if let Some(prev) = positions.insert((row, col), i) {
+                problems.push(format!(
+                    "item #{i}: index {row} {col} is already taken by item #{prev}"
+                ));
+            }
+        }
+
+        if let Some(id) = item.id.as_ref() {
+            if let Some(prev) = ids.insert(id, i) {
+                problems.push(format!(
+                    "item #{i}: id `{id}` is already used by item #{prev}"
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        let kdl = r#"
+btn {
+    namespace "zero"
+    edge "bottom"
+    thickness 0
+    length "40%"
+}
+wrap-box {
+    namespace "box"
+    edge "bottom"
+    thickness 20
+    length "40%"
+    item "text" {
+        index 0 0
+        id "a"
+    }
+    item "text" {
+        index 0 0
+        id "a"
+    }
+    item "text" {
+        index -2 0
+    }
+}
+"#;
+        let root: Root = knus::parse("test", kdl).unwrap();
+        let errors = validate(&root);
+        assert_eq!(errors.len(), 4, "{errors:?}");
+        assert!(errors[0].starts_with("btn `zero`: thickness"));
+        assert!(errors[1].contains("already taken by item #0"));
+    }
+
+    #[test]
+    fn test_decode_reports_every_widget() {
+        use miette::Diagnostic;
+
+        let kdl = r#"
+btn {
+    edge "bottom"
+    thickness "abc"
+    length "40%"
+}
+btn {
+    edge "bottom"
+    thickness 20
+    length "40%"
+    color "rgb(300, 0, 0)"
+}
+"#;
+        let err = knus::parse::<Root>("test", kdl).unwrap_err();
+        assert_eq!(err.related().map(|r| r.count()), Some(2));
+    }
+}
@@ -0,0 +1,655 @@
+use std::{path::Path, str::FromStr};
+
+use serde_json::{Map, Value};
+
+use crate::{
+    def::{Root, WidgetConf},
+    load::parse_config_file,
+};
+
+const INDENT: &str = "  ";
+
+/// what `config convert` and `config fmt` write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Kdl,
+    Jsonc,
+}
+impl ConfigFormat {
+    /// by the file extension, the same way config files are loaded.
+    pub fn from_path(p: &Path) -> Option<Self> {
+        match p.extension()?.to_str()? {
+            "kdl" => Some(Self::Kdl),
+            "json" | "jsonc" => Some(Self::Jsonc),
+            _ => None,
+        }
+    }
+}
+impl FromStr for ConfigFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "kdl" => Ok(Self::Kdl),
+            "json" | "jsonc" => Ok(Self::Jsonc),
+            _ => Err(format!(
+                "Unknown config format `{s}`, expected `kdl` or `jsonc`"
+            )),
+        }
+    }
+}
+
+/// load one config file without following its includes, and write it as `format`.
+/// themes, templates and defaults are resolved into the widgets.
+pub fn convert_config(path: &Path, format: ConfigFormat) -> Result<String, String> {
+    let root = parse_config_file(path)?;
+    write_config(&root, format)
+}
+
+/// what writing `path` back with `config fmt` would lose: comments, and the `theme`, `template`,
+/// `defaults` and `strict` nodes, which are resolved into the widgets or dropped.
+pub fn fmt_losses(path: &Path) -> Result<Vec<String>, String> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|e| format!("Fail to read {}: {e}", path.display()))?;
+    let format = ConfigFormat::from_path(path).unwrap_or(ConfigFormat::Kdl);
+    Ok(losses(&source, format))
+}
+
+fn losses(source: &str, format: ConfigFormat) -> Vec<String> {
+    let (comments, nodes) = scan(source, format == ConfigFormat::Kdl);
+    let resolved: Vec<String> = match format {
+        ConfigFormat::Kdl => nodes.into_iter().map(str::to_string).collect(),
+        // keys of the top level object
+        ConfigFormat::Jsonc => serde_jsonrc::from_str::<serde_jsonrc::Value>(source)
+            .ok()
+            .and_then(|v| v.as_object().map(|o| o.keys().cloned().collect()))
+            .unwrap_or_default(),
+    };
+    let mut losses = vec![];
+    for name in ["theme", "template", "templates", "defaults", "strict"] {
+        if resolved.iter().any(|n| n == name) {
+            losses.push(format!("`{name}`"));
+        }
+    }
+    if comments {
+        losses.push("comments".to_string());
+    }
+    losses
+}
+
+// whether there are comments, and the names of the top level kdl nodes.
+// strings are skipped, a `//` in a url is no comment.
+fn scan(source: &str, kdl: bool) -> (bool, Vec<&str>) {
+    let b = source.as_bytes();
+    let mut comments = false;
+    let mut nodes = vec![];
+    let mut depth = 0_usize;
+    let mut node_start = true;
+    let mut i = 0;
+    while i < b.len() {
+        let rest = &b[i..];
+        if rest.starts_with(b"//") {
+            comments = true;
+            i += rest.iter().position(|&c| c == b'\n').unwrap_or(rest.len());
+            continue;
+        }
+        if rest.starts_with(b"/*") {
+            comments = true;
+            // kdl block comments nest
+            let mut level = 0;
+            while i < b.len() {
+                if b[i..].starts_with(b"/*") {
+                    level += 1;
+                    i += 2;
+                } else if b[i..].starts_with(b"*/") {
+                    level -= 1;
+                    i += 2;
+                    if level == 0 {
+                        break;
+                    }
+                } else {
+                    i += 1;
+                }
+            }
+            continue;
+        }
+        // `/-` comments out the next node, argument or block
+        if kdl && rest.starts_with(b"/-") {
+            comments = true;
+            i += 2;
+            continue;
+        }
+        match b[i] {
+            b'"' => {
+                i = string_end(b, i + 1);
+                node_start = false;
+            }
+            // r"..." and r#"..."#
+            b'r' if kdl && rest[1..].iter().find(|&&c| c != b'#') == Some(&b'"') => {
+                let hashes = rest[1..].iter().take_while(|&&c| c == b'#').count();
+                let close = [b"\"".as_slice(), &vec![b'#'; hashes]].concat();
+                let start = i + 2 + hashes;
+                i = b[start..]
+                    .windows(close.len())
+                    .position(|w| w == close)
+                    .map_or(b.len(), |p| start + p + close.len());
+                node_start = false;
+            }
+            b'{' => {
+                depth += 1;
+                node_start = true;
+                i += 1;
+            }
+            b'}' => {
+                depth = depth.saturating_sub(1);
+                node_start = true;
+                i += 1;
+            }
+            b'\n' | b';' => {
+                node_start = true;
+                i += 1;
+            }
+            c if c.is_ascii_whitespace() => i += 1,
+            _ => {
+                let end = rest
+                    .iter()
+                    .position(|c| c.is_ascii_whitespace() || b"{};\"=/()".contains(c))
+                    .map_or(b.len(), |p| i + p);
+                if kdl && node_start && depth == 0 && end > i {
+                    nodes.push(&source[i..end]);
+                }
+                node_start = false;
+                i = end.max(i + 1);
+            }
+        }
+    }
+    (comments, nodes)
+}
+
+// index after the closing quote of a string starting at `i`
+fn string_end(b: &[u8], mut i: usize) -> usize {
+    while i < b.len() {
+        match b[i] {
+            b'\\' => i += 2,
+            b'"' => return i + 1,
+            _ => i += 1,
+        }
+    }
+    b.len()
+}
+
+/// fields are in the order they are declared, the ones with default values are left out.
+pub fn write_config(root: &Root, format: ConfigFormat) -> Result<String, String> {
+    let widgets = root
+        .widgets
+        .iter()
+        .map(minimal_widget)
+        .collect::<Result<Vec<_>, _>>()?;
+    match format {
+        ConfigFormat::Kdl => Ok(write_kdl(&root.include, &widgets)),
+        ConfigFormat::Jsonc => write_jsonc(&root.include, widgets),
+    }
+}
+
+// the widget as json, without the fields it decodes the same without
+fn minimal_widget(w: &WidgetConf) -> Result<Value, String> {
+    let full = serde_json::to_value(w)
+        .map_err(|e| format!("Fail to serialize {} widget: {e}", w.type_name()))?;
+    let mut min = full.clone();
+    prune(&mut min, "", &full);
+    Ok(min)
+}
+
+fn decodes_to(value: &Value, full: &Value) -> bool {
+    serde_json::from_value::<WidgetConf>(value.clone())
+        .ok()
+        .and_then(|w| serde_json::to_value(w).ok())
+        .is_some_and(|v| &v == full)
+}
+
+// depth first, `pointer` is the json pointer of the object or list to go through
+fn prune(widget: &mut Value, pointer: &str, full: &Value) {
+    let keys = match widget.pointer(pointer) {
+        Some(Value::Object(map)) => map.keys().cloned().collect::<Vec<_>>(),
+        // items of a list are kept, but not everything inside them
+        Some(Value::Array(list)) => {
+            let len = list.len();
+            for i in 0..len {
+                prune(widget, &format!("{pointer}/{i}"), full);
+            }
+            return;
+        }
+        _ => return,
+    };
+    for key in keys {
+        let mut candidate = widget.clone();
+        if let Some(Value::Object(map)) = candidate.pointer_mut(pointer) {
+            map.retain(|k, _| k != &key);
+        }
+        if decodes_to(&candidate, full) {
+            *widget = candidate;
+        } else {
+            let key = key.replace('~', "~0").replace('/', "~1");
+            prune(widget, &format!("{pointer}/{key}"), full);
+        }
+    }
+}
+
+fn write_jsonc(include: &[String], widgets: Vec<Value>) -> Result<String, String> {
+    let mut root = Map::new();
+    if !include.is_empty() {
+        root.insert("include".to_string(), include.into());
+    }
+    root.insert("widgets".to_string(), Value::Array(widgets));
+    serde_json::to_string_pretty(&root)
+        .map(|s| s + "\n")
+        .map_err(|e| format!("Fail to write json: {e}"))
+}
+
+// one line of kdl, children are written as a block
+#[derive(Debug, Default)]
+struct Node {
+    name: String,
+    // literals, and `name=value` properties after them
+    args: Vec<String>,
+    children: Vec<Node>,
+}
+impl Node {
+    fn new(name: &str) -> Self {
+        Self {
+            name: kdl_ident(name),
+            ..Default::default()
+        }
+    }
+
+    fn write(&self, out: &mut String, depth: usize) {
+        out.push_str(&INDENT.repeat(depth));
+        out.push_str(&self.name);
+        for arg in self.args.iter() {
+            out.push(' ');
+            out.push_str(arg);
+        }
+        if !self.children.is_empty() {
+            out.push_str(" {\n");
+            for child in self.children.iter() {
+                child.write(out, depth + 1);
+            }
+            out.push_str(&INDENT.repeat(depth));
+            out.push('}');
+        }
+        out.push('\n');
+    }
+}
+
+fn write_kdl(include: &[String], widgets: &[Value]) -> String {
+    let mut nodes = vec![];
+    if !include.is_empty() {
+        nodes.push(Node {
+            args: include.iter().map(|s| kdl_string(s)).collect(),
+            ..Node::new("include")
+        });
+    }
+    for w in widgets {
+        let Value::Object(map) = w else {
+            continue;
+        };
+        // the type is the node name
+        let mut node = Node::new(map.get("type").and_then(Value::as_str).unwrap_or_default());
+        for (k, v) in map.iter().filter(|(k, _)| *k != "type") {
+            node.children.extend(field_nodes(k, v));
+        }
+        nodes.push(node);
+    }
+
+    let mut out = String::new();
+    for (i, node) in nodes.iter().enumerate() {
+        if i != 0 {
+            out.push('\n');
+        }
+        node.write(&mut out, 0);
+    }
+    out
+}
+
+// a field named after its json key, lists of blocks like `items` are one node per item
+fn field_nodes(key: &str, value: &Value) -> Vec<Node> {
+    match (key, value) {
+        ("event-map", Value::Object(map)) => vec![Node {
+            children: map.iter().map(|(k, v)| binding_node(k, v)).collect(),
+            ..Node::new(key)
+        }],
+        // `env "NAME" "value"` of `when`
+        ("env", Value::Object(map)) => map
+            .iter()
+            .map(|(name, v)| Node {
+                args: [kdl_string(name)]
+                    .into_iter()
+                    .chain(v.as_str().map(kdl_string))
+                    .collect(),
+                ..Node::new(key)
+            })
+            .collect(),
+        (_, Value::Array(items)) if items.first().is_some_and(Value::is_object) => {
+            let name = match key {
+                "items" => "item",
+                k => k,
+            };
+            items.iter().map(|item| block_node(name, item)).collect()
+        }
+        (_, Value::Array(items)) => vec![Node {
+            args: items.iter().map(kdl_value).collect(),
+            ..Node::new(key)
+        }],
+        (_, Value::Object(_)) => vec![block_node(key, value)],
+        // a flag
+        (_, Value::Bool(true)) => vec![Node::new(key)],
+        _ => vec![Node {
+            args: vec![kdl_value(value)],
+            ..Node::new(key)
+        }],
+    }
+}
+
+// `{"type": "name", ...}` is `key "name" { ... }`
+fn block_node(key: &str, value: &Value) -> Node {
+    let mut node = Node::new(key);
+    if let Value::Object(map) = value {
+        for (k, v) in map {
+            match (k.as_str(), v) {
+                ("type", Value::String(t)) => node.args.insert(0, kdl_string(t)),
+                _ => node.children.extend(field_nodes(k, v)),
+            }
+        }
+    }
+    node
+}
+
+// `mouse-left "command"`, or a block of `shell` and `action` for a list
+fn binding_node(key: &str, value: &Value) -> Node {
+    let mut node = Node::new(key);
+    let command = match value {
+        Value::Object(map) => {
+            if let Some(t) = map.get("threshold") {
+                node.args.push(format!("threshold={}", kdl_value(t)));
+            }
+            map.get("command").unwrap_or(&Value::Null)
+        }
+        v => v,
+    };
+    match command {
+        Value::Array(actions) => {
+            node.children = actions
+                .iter()
+                .map(|action| match action.as_array().map(|a| a.split_first()) {
+                    Some(Some((name, args))) => Node {
+                        args: [name].into_iter().chain(args).map(kdl_value).collect(),
+                        ..Node::new("action")
+                    },
+                    _ => Node {
+                        args: vec![kdl_value(action)],
+                        ..Node::new("shell")
+                    },
+                })
+                .collect()
+        }
+        v => node.args.insert(0, kdl_value(v)),
+    }
+    node
+}
+
+fn kdl_value(v: &Value) -> String {
+    match v {
+        Value::Null => "null".to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => kdl_string(s),
+        v => kdl_string(&v.to_string()),
+    }
+}
+
+fn kdl_string(s: &str) -> String {
+    let mut out = String::from('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            c if c.is_control() => out.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+// bare unless it would be read as a number, keyword or something else
+fn kdl_ident(name: &str) -> String {
+    let number = |s: &str| s.starts_with(|c: char| c.is_ascii_digit());
+    let bare = !name.is_empty()
+        && !number(name)
+        && !(name.starts_with(['-', '+']) && number(&name[1..]))
+        && !name.starts_with("r#")
+        && !name
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || "\\/(){}<>;[]=,\"".contains(c))
+        && !matches!(name, "true" | "false" | "null");
+    if bare {
+        name.to_string()
+    } else {
+        kdl_string(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::def::parse_jsonc;
+
+    fn to_value(root: &Root) -> Value {
+        serde_json::to_value(root).unwrap()
+    }
+
+    // written as kdl and jsonc, both read back the same, and writing again changes nothing
+    fn assert_lossless(kdl: &str) {
+        let root: Root = knus::parse("test", kdl).unwrap();
+
+        let out = write_config(&root, ConfigFormat::Kdl).unwrap();
+        let from_kdl: Root = knus::parse("out", &out).unwrap();
+        assert_eq!(to_value(&from_kdl), to_value(&root), "{out}");
+        assert_eq!(write_config(&from_kdl, ConfigFormat::Kdl).unwrap(), out);
+
+        let out = write_config(&root, ConfigFormat::Jsonc).unwrap();
+        let from_json = parse_jsonc(&out).unwrap();
+        assert_eq!(to_value(&from_json), to_value(&root), "{out}");
+        assert_eq!(write_config(&from_json, ConfigFormat::Jsonc).unwrap(), out);
+    }
+
+    #[test]
+    fn test_fmt_losses() {
+        let kdl = r##"
+theme {
+    accent "#ff0000"
+}
+btn {
+    edge "top"
+    monitor r"re:^DP-\d" "https://example.com/a/*b*/"
+    template "not at the top"
+}
+"##;
+        assert_eq!(losses(kdl, ConfigFormat::Kdl), ["`theme`"]);
+        assert_eq!(
+            losses("strict\nbtn { /-edge \"top\"; }", ConfigFormat::Kdl),
+            ["`strict`", "comments"]
+        );
+        assert_eq!(
+            losses("btn { edge \"top\"; } // end", ConfigFormat::Kdl),
+            ["comments"]
+        );
+
+        let json = r#"{
+    "templates": {},
+    "widgets": [{ "type": "btn", "namespace": "http://a" }]
+}"#;
+        assert_eq!(losses(json, ConfigFormat::Jsonc), ["`templates`"]);
+        assert_eq!(
+            losses("{ /* a */ \"widgets\": [] }", ConfigFormat::Jsonc),
+            ["comments"]
+        );
+        assert!(losses(r#"{ "widgets": [] }"#, ConfigFormat::Jsonc).is_empty());
+    }
+
+    #[test]
+    fn test_convert_lossless() {
+        assert_lossless(
+            r##"
+include "widgets/audio.kdl"
+btn {
+    edge "bottom"
+    position "left"
+    namespace "launcher"
+    monitor "!eDP-1" r"model:re:U27\d\dQ" "focused"
+    thickness 20
+    length "33.3%"
+    offset 0.5
+    margins {
+        left "10%"
+    }
+    color "#ff000080"
+    when {
+        compositor "niri"
+        env "HOME"
+        env "XDG_SESSION_TYPE" "wayland"
+        output-count ">=2"
+    }
+    event-map {
+        mouse-left "echo \"left\"\n"
+        scroll-up "up" threshold=30
+        mouse-right {
+            action "toggle-pin" "audio"
+            action "pop" "clock" 2000
+            shell "notify-send pinned"
+        }
+        scroll-down threshold=10 {
+            action "switch-workspace" 3
+        }
+    }
+}
+slider {
+    edge "right"
+    thickness 20
+    length 300
+    redraw-only-on-internal-update
+    preset "ipc" {
+        initial-value 0.5
+        on-change-command "notify-send {float:2,100}%"
+    }
+}
+wrap-box {
+    edge "top"
+    thickness 20
+    length "40%"
+    outlook "window" {
+        border-radius 10
+    }
+    item "ring" {
+        index 0 1
+        preset "cpu" {
+            core 1
+        }
+    }
+    item "text" {
+        index 1 0
+        preset "time" {
+            format "%H:%M"
+        }
+    }
+}
+workspace {
+    edge "top"
+    monitor 1
+    thickness 10
+    length "50%"
+    focused-only
+    preset "niri"
+}
+"##,
+        );
+    }
+
+    #[test]
+    fn test_convert_omit_defaults() {
+        let kdl = r##"
+btn {
+    edge "bottom"
+    layer "top"
+    monitor 0
+    namespace "launcher"
+    thickness 20
+    length "40%"
+    color "#7B98FF"
+    event-map {
+        mouse-left "nwg-drawer"
+    }
+}
+"##;
+        let root: Root = knus::parse("test", kdl).unwrap();
+        assert_eq!(
+            write_config(&root, ConfigFormat::Kdl).unwrap(),
+            r#"btn {
+  edge "bottom"
+  namespace "launcher"
+  thickness 20
+  length "40%"
+  event-map {
+    mouse-left "nwg-drawer"
+  }
+}
+"#
+        );
+        assert_eq!(
+            write_config(&root, ConfigFormat::Jsonc).unwrap(),
+            r#"{
+  "widgets": [
+    {
+      "type": "btn",
+      "edge": "bottom",
+      "namespace": "launcher",
+      "thickness": 20,
+      "length": "40%",
+      "event-map": {
+        "mouse-left": "nwg-drawer"
+      }
+    }
+  ]
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_kdl_names_and_strings() {
+        assert_eq!(kdl_ident("mouse-left"), "mouse-left");
+        assert_eq!(kdl_ident("272"), "\"272\"");
+        assert_eq!(kdl_ident("-1"), "\"-1\"");
+        assert_eq!(kdl_ident("null"), "\"null\"");
+        assert_eq!(kdl_string("a \"b\"\\\n"), r#""a \"b\"\\\n""#);
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("config.jsonc")),
+            Some(ConfigFormat::Jsonc)
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("a/config.kdl")),
+            Some(ConfigFormat::Kdl)
+        );
+        assert_eq!(ConfigFormat::from_path(Path::new("config")), None);
+        assert!("yaml".parse::<ConfigFormat>().is_err());
+    }
+}
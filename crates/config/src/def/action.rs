@@ -0,0 +1,258 @@
+use std::{cell::RefCell, ops::Deref};
+
+use knus::{ast::Literal, errors::DecodeError, traits::ErrorSpan};
+use serde_jsonrc::Value;
+use util::shell::shell_cmd_non_block;
+
+const DEFAULT_POP_DURATION: u64 = 1000;
+
+pub const ACTION_NAMES: &[&str] = &[
+    "shell",
+    "toggle-pin",
+    "show",
+    "hide",
+    "pop",
+    "set",
+    "reload",
+    "switch-workspace",
+];
+
+/// what an event map binding runs, everything but `Shell` is done by the daemon itself.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    Shell(String),
+    TogglePin(String),
+    Show(String),
+    Hide(String),
+    Pop { namespace: String, duration: u64 },
+    Set { namespace: String, value: f64 },
+    Reload,
+    SwitchWorkspace(u32),
+}
+impl Action {
+    /// `args` are strings and numbers, as in `action "pop" "clock" 2000`.
+    pub fn from_args(name: &str, args: &[Value]) -> Result<Self, String> {
+        let string = |i: usize| match args.get(i) {
+            Some(Value::String(s)) => Ok(s.clone()),
+            Some(v) => Err(format!(
+                "`{name}` expects a string as argument {}, got {v}",
+                i + 1
+            )),
+            None => Err(format!("`{name}` expects a string as argument {}", i + 1)),
+        };
+        let number = |i: usize| match args.get(i) {
+            Some(Value::Number(n)) => Ok(n.as_f64().unwrap_or_default()),
+            Some(v) => Err(format!(
+                "`{name}` expects a number as argument {}, got {v}",
+                i + 1
+            )),
+            None => Err(format!("`{name}` expects a number as argument {}", i + 1)),
+        };
+        let max_args = |n: usize| {
+            if args.len() > n {
+                Err(format!("`{name}` takes at most {n} argument(s)"))
+            } else {
+                Ok(())
+            }
+        };
+
+        let action = match name {
+            "shell" => Self::Shell(string(0)?),
+            "toggle-pin" => Self::TogglePin(string(0)?),
+            "show" => Self::Show(string(0)?),
+            "hide" => Self::Hide(string(0)?),
+            "pop" => Self::Pop {
+                namespace: string(0)?,
+                duration: match args.get(1) {
+                    Some(_) => number(1)?.max(0.) as u64,
+                    None => DEFAULT_POP_DURATION,
+                },
+            },
+            "set" => Self::Set {
+                namespace: string(0)?,
+                value: number(1)?,
+            },
+            "reload" => Self::Reload,
+            "switch-workspace" => {
+                let n = number(0)?;
+                if n < 1. || n.fract() != 0. {
+                    return Err(format!(
+                        "`{name}` expects a workspace number from 1, got {n}"
+                    ));
+                }
+                Self::SwitchWorkspace(n as u32)
+            }
+            _ => {
+                return Err(format!(
+                    "Unknown action `{name}`, expected one of: {}",
+                    ACTION_NAMES.join(", ")
+                ))
+            }
+        };
+        max_args(match action {
+            Self::Reload => 0,
+            Self::Pop { .. } | Self::Set { .. } => 2,
+            _ => 1,
+        })?;
+        Ok(action)
+    }
+
+    /// `"command"`, or a list of `"command"` and `["action", args...]`.
+    pub fn from_json(value: Value) -> Result<Vec<Self>, String> {
+        let from_item = |v: Value| match v {
+            Value::String(s) => Ok(Self::Shell(s)),
+            Value::Array(a) => match a.split_first() {
+                Some((Value::String(name), args)) => Self::from_args(name, args),
+                _ => Err("an action is an array of its name and arguments".to_string()),
+            },
+            v => Err(format!("Invalid action: {v}")),
+        };
+        match value {
+            Value::Array(a) => a.into_iter().map(from_item).collect(),
+            v => Ok(vec![from_item(v)?]),
+        }
+    }
+
+    /// the other way around of `from_json`.
+    pub fn to_json(actions: &[Self]) -> Value {
+        let item = |action: &Self| {
+            let (name, args) = match action {
+                Self::Shell(cmd) => return Value::String(cmd.clone()),
+                Self::TogglePin(ns) => ("toggle-pin", vec![Value::from(ns.as_str())]),
+                Self::Show(ns) => ("show", vec![Value::from(ns.as_str())]),
+                Self::Hide(ns) => ("hide", vec![Value::from(ns.as_str())]),
+                Self::Pop {
+                    namespace,
+                    duration,
+                } => {
+                    let mut args = vec![Value::from(namespace.as_str())];
+                    if *duration != DEFAULT_POP_DURATION {
+                        args.push(Value::from(*duration));
+                    }
+                    ("pop", args)
+                }
+                Self::Set { namespace, value } => (
+                    "set",
+                    vec![Value::from(namespace.as_str()), Value::from(*value)],
+                ),
+                Self::Reload => ("reload", vec![]),
+                Self::SwitchWorkspace(n) => ("switch-workspace", vec![Value::from(*n)]),
+            };
+            Value::Array([Value::from(name)].into_iter().chain(args).collect())
+        };
+        Value::Array(actions.iter().map(item).collect())
+    }
+
+    /// children of a binding: `action "name" args...` or `shell "command"`.
+    pub fn decode_children<S: ErrorSpan>(
+        node: &knus::ast::SpannedNode<S>,
+    ) -> Result<Vec<Self>, DecodeError<S>> {
+        node.children()
+            .map(|child| {
+                let mut args = child
+                    .arguments
+                    .iter()
+                    .map(|arg| match arg.literal.deref() {
+                        Literal::String(s) => Ok(Value::String(s.to_string())),
+                        Literal::Int(i) => TryInto::<i64>::try_into(i)
+                            .map(Value::from)
+                            .map_err(|e| DecodeError::conversion(&arg.literal, e)),
+                        Literal::Decimal(d) => TryInto::<f64>::try_into(d)
+                            .map(Value::from)
+                            .map_err(|e| DecodeError::conversion(&arg.literal, e)),
+                        _ => Err(DecodeError::unsupported(
+                            &arg.literal,
+                            "Expected a string or a number",
+                        )),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                let name = match child.node_name.as_ref() {
+                    "shell" => "shell".to_string(),
+                    "action" if !args.is_empty() => match args.remove(0) {
+                        Value::String(s) => s,
+                        _ => {
+                            return Err(DecodeError::unsupported(
+                                &child.arguments[0].literal,
+                                "Expected the name of the action",
+                            ))
+                        }
+                    },
+                    _ => {
+                        return Err(DecodeError::unexpected(
+                            &child.node_name,
+                            "node",
+                            "Expected `action \"<name>\" <args>...` or `shell \"<command>\"`",
+                        ))
+                    }
+                };
+                Self::from_args(&name, &args)
+                    .map_err(|e| DecodeError::conversion(&child.node_name, e))
+            })
+            .collect()
+    }
+}
+
+thread_local! {
+    static HANDLER: RefCell<Option<Box<dyn Fn(Action)>>> = RefCell::new(None);
+}
+
+/// where actions other than `shell` go, the daemon sends them to its event loop.
+pub fn set_action_handler(f: impl Fn(Action) + 'static) {
+    HANDLER.set(Some(Box::new(f)));
+}
+
+pub fn run_actions(actions: &[Action]) {
+    for action in actions {
+        match action {
+            Action::Shell(cmd) => shell_cmd_non_block(cmd.clone()),
+            action => HANDLER.with_borrow(|h| match h {
+                Some(h) => h(action.clone()),
+                None => log::warn!("No handler for action: {action:?}"),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_jsonrc::json;
+
+    #[test]
+    fn test_action_from_json() {
+        let actions = Action::from_json(json!([
+            ["toggle-pin", "audio"],
+            ["pop", "clock", 2000],
+            ["set", "mic", 0],
+            ["reload"],
+            "notify-send done",
+        ]))
+        .unwrap();
+        assert_eq!(
+            actions,
+            [
+                Action::TogglePin("audio".to_string()),
+                Action::Pop {
+                    namespace: "clock".to_string(),
+                    duration: 2000
+                },
+                Action::Set {
+                    namespace: "mic".to_string(),
+                    value: 0.
+                },
+                Action::Reload,
+                Action::Shell("notify-send done".to_string()),
+            ]
+        );
+
+        assert_eq!(
+            Action::from_json(Action::to_json(&actions)).unwrap(),
+            actions
+        );
+
+        assert!(Action::from_json(json!([["switch-workspace", 0]])).is_err());
+        assert!(Action::from_json(json!([["reload", "now"]])).is_err());
+        assert!(Action::from_json(json!([["pin", "audio"]])).is_err());
+    }
+}
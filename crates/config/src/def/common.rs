@@ -1,18 +1,22 @@
-use knus::{errors::DecodeError, Decode};
+use knus::errors::DecodeError;
 use schemars::{json_schema, JsonSchema};
-use serde::{Deserialize, Deserializer};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_jsonrc::Value;
 use smithay_client_toolkit::shell::wlr_layer::{Anchor, Layer};
 use std::collections::HashSet;
 use std::ops::Deref;
 
 use crate::def::shared::{Curve, NumOrRelative};
+use crate::def::when::When;
+use way_edges_derive::Config;
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum MonitorSpecifier {
     Lists {
         ids: HashSet<usize>,
         names: HashSet<String>,
+        matchers: Vec<MonitorMatcher>,
+        excludes: Vec<MonitorMatcher>,
     },
     All,
 }
@@ -21,9 +25,209 @@ impl Default for MonitorSpecifier {
         Self::Lists {
             ids: HashSet::from([0]),
             names: HashSet::new(),
+            matchers: vec![],
+            excludes: vec![],
         }
     }
 }
+// one string of the monitor list
+enum MonitorArg {
+    Name(String),
+    Matcher(MonitorMatcher),
+    Exclude(MonitorMatcher),
+}
+impl MonitorArg {
+    fn parse(s: &str) -> Result<Self, String> {
+        if s == "*" {
+            return Err("You cannot use the wildcard character '*' in a list of monitors, it is only allowed as the sole argument to specify all monitors".to_string());
+        }
+        if let Some(s) = s.strip_prefix('!') {
+            return MonitorMatcher::parse(s).map(Self::Exclude);
+        }
+        Ok(match MonitorMatcher::parse(s)? {
+            MonitorMatcher::Field(MonitorField::Name, TextMatch::Exact(name)) => Self::Name(name),
+            m => Self::Matcher(m),
+        })
+    }
+}
+
+impl MonitorSpecifier {
+    fn from_args(ids: HashSet<usize>, args: Vec<MonitorArg>) -> Self {
+        let mut names = HashSet::new();
+        let mut matchers = vec![];
+        let mut excludes = vec![];
+        for arg in args {
+            match arg {
+                MonitorArg::Name(name) => {
+                    names.insert(name);
+                }
+                MonitorArg::Matcher(m) => matchers.push(m),
+                MonitorArg::Exclude(m) => excludes.push(m),
+            }
+        }
+        Self::Lists {
+            ids,
+            names,
+            matchers,
+            excludes,
+        }
+    }
+
+    pub fn matches(&self, output: &OutputDesc) -> bool {
+        let Self::Lists {
+            ids,
+            names,
+            matchers,
+            excludes,
+        } = self
+        else {
+            return true;
+        };
+        if excludes.iter().any(|m| m.matches(output)) {
+            return false;
+        }
+        // only exclusions, every other output is fine
+        if ids.is_empty() && names.is_empty() && matchers.is_empty() {
+            return true;
+        }
+        ids.contains(&output.index)
+            || output.name.is_some_and(|n| names.contains(n))
+            || matchers.iter().any(|m| m.matches(output))
+    }
+
+    /// whether the focused output has to be asked from the compositor.
+    pub fn uses_focused(&self) -> bool {
+        match self {
+            Self::Lists {
+                matchers, excludes, ..
+            } => matchers
+                .iter()
+                .chain(excludes)
+                .any(|m| matches!(m, MonitorMatcher::Focused)),
+            Self::All => false,
+        }
+    }
+}
+
+/// what is known about an output when matching it against `monitor`.
+#[derive(Debug, Default)]
+pub struct OutputDesc<'a> {
+    pub index: usize,
+    pub name: Option<&'a str>,
+    pub make: Option<&'a str>,
+    pub model: Option<&'a str>,
+    pub description: Option<&'a str>,
+    pub focused: bool,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum MonitorField {
+    Name,
+    Make,
+    Model,
+    Serial,
+    Description,
+}
+
+#[derive(Debug, Clone)]
+pub enum TextMatch {
+    Exact(String),
+    Regex(regex_lite::Regex),
+}
+impl TextMatch {
+    pub(crate) fn parse(s: &str) -> Result<Self, String> {
+        match s.strip_prefix("re:") {
+            Some(re) => regex_lite::Regex::new(re)
+                .map(Self::Regex)
+                .map_err(|e| format!("Invalid regex `{re}`: {e}")),
+            None => Ok(Self::Exact(s.to_string())),
+        }
+    }
+    pub(crate) fn matches(&self, s: &str) -> bool {
+        match self {
+            Self::Exact(e) => e == s,
+            Self::Regex(re) => re.is_match(s),
+        }
+    }
+}
+impl PartialEq for TextMatch {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Exact(a), Self::Exact(b)) => a == b,
+            (Self::Regex(a), Self::Regex(b)) => a.as_str() == b.as_str(),
+            _ => false,
+        }
+    }
+}
+impl Eq for TextMatch {}
+impl std::fmt::Display for TextMatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Exact(s) => f.write_str(s),
+            Self::Regex(re) => write!(f, "re:{}", re.as_str()),
+        }
+    }
+}
+
+/// `focused`, `re:<regex>` for the connector name, or `make:`, `model:`, `serial:`, `desc:`
+/// followed by the exact value or `re:<regex>`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum MonitorMatcher {
+    Field(MonitorField, TextMatch),
+    Focused,
+}
+impl MonitorMatcher {
+    fn parse(s: &str) -> Result<Self, String> {
+        if s == "focused" {
+            return Ok(Self::Focused);
+        }
+        let (field, value) = match s.split_once(':') {
+            Some(("make", v)) => (MonitorField::Make, v),
+            Some(("model", v)) => (MonitorField::Model, v),
+            Some(("serial", v)) => (MonitorField::Serial, v),
+            Some(("desc", v)) => (MonitorField::Description, v),
+            _ => (MonitorField::Name, s),
+        };
+        Ok(Self::Field(field, TextMatch::parse(value)?))
+    }
+
+    fn matches(&self, output: &OutputDesc) -> bool {
+        let (field, text) = match self {
+            Self::Focused => return output.focused,
+            Self::Field(field, text) => (field, text),
+        };
+        let value = match field {
+            MonitorField::Name => output.name,
+            MonitorField::Make => output.make,
+            MonitorField::Model => output.model,
+            MonitorField::Description => output.description,
+            // wayland has no serial of its own, compositors put it in the description
+            MonitorField::Serial => {
+                return output
+                    .description
+                    .is_some_and(|d| d.split_whitespace().any(|w| text.matches(w)))
+            }
+        };
+        value.is_some_and(|v| text.matches(v))
+    }
+}
+impl std::fmt::Display for MonitorMatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (field, text) = match self {
+            Self::Focused => return f.write_str("focused"),
+            Self::Field(field, text) => (field, text),
+        };
+        let prefix = match field {
+            MonitorField::Name => "",
+            MonitorField::Make => "make:",
+            MonitorField::Model => "model:",
+            MonitorField::Serial => "serial:",
+            MonitorField::Description => "desc:",
+        };
+        write!(f, "{prefix}{text}")
+    }
+}
+
 impl<S: knus::traits::ErrorSpan> knus::Decode<S> for MonitorSpecifier {
     fn decode_node(
         node: &knus::ast::SpannedNode<S>,
@@ -49,18 +253,15 @@ impl<S: knus::traits::ErrorSpan> knus::Decode<S> for MonitorSpecifier {
         }
 
         let mut ids = HashSet::new();
-        let mut names = HashSet::new();
+        let mut args = vec![];
 
         for arg in &node.arguments {
             match arg.literal.deref() {
                 knus::ast::Literal::String(s) => {
-                    if s.deref() == "*" {
-                        return Err(DecodeError::unsupported(
-                            &arg.literal,
-                            "You cannot use the wildcard character '*' in a list of monitors, it is only allowed as the sole argument to specify all monitors",
-                        ));
-                    }
-                    names.insert(s.to_string());
+                    args.push(
+                        MonitorArg::parse(s)
+                            .map_err(|e| DecodeError::conversion(&arg.literal, e))?,
+                    );
                 }
                 knus::ast::Literal::Int(value) => {
                     if let Ok(id) = value.try_into() {
@@ -81,7 +282,7 @@ impl<S: knus::traits::ErrorSpan> knus::Decode<S> for MonitorSpecifier {
             }
         }
 
-        Ok(MonitorSpecifier::Lists { ids, names })
+        Ok(MonitorSpecifier::from_args(ids, args))
     }
 }
 impl JsonSchema for MonitorSpecifier {
@@ -90,14 +291,16 @@ impl JsonSchema for MonitorSpecifier {
     }
 
     fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        let matcher = json_schema!({
+            "type": "string",
+            "description": "connector name, `focused`, `re:<regex>` for the connector name, or `make:`, `model:`, `serial:`, `desc:` followed by the value or `re:<regex>`, `!` before any of them excludes the monitors it matches",
+        });
         json_schema!({
-            "oneOf": [
-                {
-                    "type": "string",
-                },
+            "anyOf": [
                 {
                     "enum": ["*"],
                 },
+                matcher,
                 {
                     "type": "number",
                     "minimum": 0,
@@ -105,7 +308,7 @@ impl JsonSchema for MonitorSpecifier {
                 {
                     "type": "array",
                     "items": {
-                        "type": ["string", "number"],
+                        "anyOf": [matcher, { "type": "number", "minimum": 0 }],
                     },
                 }
             ],
@@ -129,10 +332,10 @@ impl<'de> Deserialize<'de> for MonitorSpecifier {
             where
                 E: serde::de::Error,
             {
-                Ok(MonitorSpecifier::Lists {
-                    ids: HashSet::from([value as usize]),
-                    names: HashSet::new(),
-                })
+                Ok(MonitorSpecifier::from_args(
+                    HashSet::from([value as usize]),
+                    vec![],
+                ))
             }
 
             fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
@@ -142,10 +345,8 @@ impl<'de> Deserialize<'de> for MonitorSpecifier {
                 if value == "*" {
                     Ok(MonitorSpecifier::All)
                 } else {
-                    Ok(MonitorSpecifier::Lists {
-                        ids: HashSet::new(),
-                        names: HashSet::from([value.to_string()]),
-                    })
+                    let arg = MonitorArg::parse(value).map_err(serde::de::Error::custom)?;
+                    Ok(MonitorSpecifier::from_args(HashSet::new(), vec![arg]))
                 }
             }
 
@@ -154,17 +355,11 @@ impl<'de> Deserialize<'de> for MonitorSpecifier {
                 A: serde::de::SeqAccess<'ae>,
             {
                 let mut ids = HashSet::new();
-                let mut names = HashSet::new();
+                let mut args = vec![];
                 while let Some(value) = seq.next_element::<Value>()? {
                     match value {
                         Value::String(s) => {
-                            if s == "*" {
-                                return Err(serde::de::Error::invalid_value(
-                                    serde::de::Unexpected::Str(&s),
-                                    &"You cannot use the wildcard character '*' in a list of monitors, it is only allowed as the sole argument to specify all monitors",
-                                ));
-                            }
-                            names.insert(s);
+                            args.push(MonitorArg::parse(&s).map_err(serde::de::Error::custom)?)
                         }
                         Value::Number(num) => {
                             if let Some(id) = num.as_u64() {
@@ -184,7 +379,7 @@ impl<'de> Deserialize<'de> for MonitorSpecifier {
                         }
                     }
                 }
-                Ok(MonitorSpecifier::Lists { ids, names })
+                Ok(MonitorSpecifier::from_args(ids, args))
             }
         }
 
@@ -192,96 +387,110 @@ impl<'de> Deserialize<'de> for MonitorSpecifier {
     }
 }
 
-#[derive(Debug, Clone, Default, Decode, Deserialize, JsonSchema)]
+// a single id as a number, anything else as a list
+impl Serialize for MonitorSpecifier {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let Self::Lists {
+            ids,
+            names,
+            matchers,
+            excludes,
+        } = self
+        else {
+            return s.serialize_str("*");
+        };
+        let mut ids = ids.iter().copied().collect::<Vec<_>>();
+        ids.sort_unstable();
+        if let ([id], true) = (
+            ids.as_slice(),
+            names.is_empty() && matchers.is_empty() && excludes.is_empty(),
+        ) {
+            return s.serialize_u64(*id as u64);
+        }
+        let mut names = names.iter().cloned().collect::<Vec<_>>();
+        names.sort_unstable();
+        ids.into_iter()
+            .map(Value::from)
+            .chain(names.into_iter().map(Value::from))
+            .chain(matchers.iter().map(|m| Value::from(m.to_string())))
+            .chain(excludes.iter().map(|m| Value::from(format!("!{m}"))))
+            .collect::<Vec<_>>()
+            .serialize(s)
+    }
+}
+
+#[derive(Debug, Clone, Default, Config)]
 pub struct Margins {
-    #[knus(child, default, unwrap(argument))]
-    #[serde(default)]
+    #[config(default)]
     pub left: NumOrRelative,
-    #[knus(child, default, unwrap(argument))]
-    #[serde(default)]
+    #[config(default)]
     pub top: NumOrRelative,
-    #[knus(child, default, unwrap(argument))]
-    #[serde(default)]
+    #[config(default)]
     pub right: NumOrRelative,
-    #[knus(child, default, unwrap(argument))]
-    #[serde(default)]
+    #[config(default)]
     pub bottom: NumOrRelative,
 }
 
-#[derive(Debug, Clone, Decode, Deserialize, JsonSchema)]
-#[serde(rename_all = "kebab-case")]
+#[derive(Debug, Clone, Config)]
 #[schemars(deny_unknown_fields)]
 pub struct CommonConfig {
-    #[knus(child, unwrap(argument, decode_with = match_edge))]
+    #[config(decode_with = match_edge)]
     #[serde(deserialize_with = "deserialize_edge")]
+    #[serde(serialize_with = "serialize_edge")]
     #[schemars(schema_with = "schema_edge")]
     pub edge: Anchor,
 
-    #[knus(child, unwrap(argument, decode_with = match_edge))]
-    #[serde(default)]
+    #[config(decode_with = match_optional_edge)]
     #[serde(deserialize_with = "deserialize_optional_edge")]
+    #[serde(serialize_with = "serialize_optional_edge")]
     #[schemars(schema_with = "schema_optional_edge")]
     pub position: Option<Anchor>,
 
-    #[knus(child, default=dt_layer(), unwrap(argument, decode_with = match_layer))]
-    #[serde(default = "dt_layer")]
+    #[config(decode_with = match_layer, default = dt_layer())]
     #[serde(deserialize_with = "deserialize_layer")]
+    #[serde(serialize_with = "serialize_layer")]
     #[schemars(schema_with = "schema_layer")]
     pub layer: Layer,
 
-    #[knus(child, default, unwrap(argument))]
-    #[serde(default)]
+    #[config(default)]
     pub offset: NumOrRelative,
 
-    #[knus(child, default)]
-    #[serde(default)]
+    #[config(node, default)]
     pub margins: Margins,
 
-    #[knus(child, default)]
-    #[serde(default)]
+    #[config(node, default)]
     pub monitor: MonitorSpecifier,
 
-    #[knus(child, default, unwrap(argument))]
-    #[serde(default)]
+    #[config(default)]
     pub namespace: String,
 
-    #[knus(child)]
-    #[serde(default)]
     pub ignore_exclusive: bool,
 
-    #[knus(child, default = dt_transition_duration(), unwrap(argument))]
-    #[serde(default = "dt_transition_duration")]
+    #[config(default = dt_transition_duration())]
     pub transition_duration: u64,
 
-    #[knus(child, default, unwrap(argument))]
-    #[serde(default)]
+    #[config(default)]
     pub animation_curve: Curve,
 
-    #[knus(child, default = dt_extra_trigger_size(), unwrap(argument))]
-    #[serde(default = "dt_extra_trigger_size")]
+    #[config(default = dt_extra_trigger_size())]
     pub extra_trigger_size: NumOrRelative,
 
-    #[knus(child, default = dt_preview_size(), unwrap(argument))]
-    #[serde(default = "dt_preview_size")]
+    #[config(default = dt_preview_size())]
     pub preview_size: NumOrRelative,
 
     // TODO: true
-    #[knus(child)]
-    #[serde(default)]
     pub pinnable: bool,
 
     // TODO: true
-    #[knus(child)]
-    #[serde(default)]
     pub pin_with_key: bool,
 
-    #[knus(child, default = dt_pin_key(), unwrap(argument))]
-    #[serde(default = "dt_pin_key")]
+    #[config(default = dt_pin_key())]
     pub pin_key: u32,
 
-    #[knus(child)]
-    #[serde(default)]
     pub pin_on_startup: bool,
+
+    #[config(node, default)]
+    pub when: When,
 }
 
 impl CommonConfig {
@@ -348,6 +557,10 @@ fn match_edge(edge: &str) -> Result<Anchor, std::io::Error> {
     })
 }
 
+fn match_optional_edge(edge: &str) -> Result<Option<Anchor>, std::io::Error> {
+    match_edge(edge).map(Some)
+}
+
 fn match_layer(layer: &str) -> Result<Layer, std::io::Error> {
     Ok(match layer {
         "background" => Layer::Background,
@@ -437,6 +650,38 @@ where
     d.deserialize_any(EventMapVisitor)
 }
 
+fn edge_name(edge: Anchor) -> &'static str {
+    match edge {
+        Anchor::TOP => "top",
+        Anchor::LEFT => "left",
+        Anchor::BOTTOM => "bottom",
+        _ => "right",
+    }
+}
+
+pub fn serialize_edge<S: Serializer>(edge: &Anchor, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_str(edge_name(*edge))
+}
+
+pub fn serialize_optional_edge<S: Serializer>(
+    edge: &Option<Anchor>,
+    s: S,
+) -> Result<S::Ok, S::Error> {
+    match edge {
+        Some(edge) => serialize_edge(edge, s),
+        None => s.serialize_none(),
+    }
+}
+
+pub fn serialize_layer<S: Serializer>(layer: &Layer, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_str(match layer {
+        Layer::Background => "background",
+        Layer::Bottom => "bottom",
+        Layer::Top => "top",
+        Layer::Overlay => "overlay",
+    })
+}
+
 pub fn schema_edge(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
     json_schema!({
         "type": "string",
@@ -455,3 +700,68 @@ pub fn schema_layer(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
         "enum": ["top", "bottom", "background", "overlay"]
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_monitor(args: &str) -> MonitorSpecifier {
+        let kdl = format!("btn {{ edge \"left\"; thickness 20; length 40; monitor {args}; }}");
+        let root: crate::def::Root = knus::parse("test", &kdl).unwrap();
+        root.widgets[0].common().monitor.clone()
+    }
+
+    #[test]
+    fn test_monitor_matchers() {
+        let laptop = OutputDesc {
+            index: 0,
+            name: Some("eDP-1"),
+            make: Some("BOE"),
+            model: Some("0x0BCA"),
+            description: Some("BOE 0x0BCA Unknown (eDP-1)"),
+            focused: false,
+        };
+        let dock = OutputDesc {
+            index: 1,
+            name: Some("DP-3"),
+            make: Some("Dell Inc."),
+            model: Some("DELL U2720Q"),
+            description: Some("Dell Inc. DELL U2720Q 8XYZ123 (DP-3)"),
+            focused: true,
+        };
+
+        let m = decode_monitor(r#""!eDP-1""#);
+        assert!(!m.matches(&laptop) && m.matches(&dock));
+
+        let m = decode_monitor(r#"r"model:re:U27\d\dQ""#);
+        assert!(!m.matches(&laptop) && m.matches(&dock));
+
+        let m = decode_monitor(r#""serial:8XYZ123" "eDP-1""#);
+        assert!(m.matches(&laptop) && m.matches(&dock));
+
+        let m = decode_monitor(r#"r"re:^DP-\d$""#);
+        assert!(!m.matches(&laptop) && m.matches(&dock));
+
+        let m = decode_monitor(r#""focused""#);
+        assert!(m.uses_focused());
+        assert!(!m.matches(&laptop) && m.matches(&dock));
+
+        // unchanged
+        let m = decode_monitor("0");
+        assert!(m.matches(&laptop) && !m.matches(&dock));
+    }
+
+    #[test]
+    fn test_json_monitor_matchers() {
+        let m: MonitorSpecifier = serde_jsonrc::from_str(r#"["!eDP-1", "make:BOE"]"#).unwrap();
+        let MonitorSpecifier::Lists {
+            matchers, excludes, ..
+        } = &m
+        else {
+            panic!("Expected Lists");
+        };
+        assert_eq!(matchers.len(), 1);
+        assert_eq!(excludes.len(), 1);
+        assert!(serde_jsonrc::from_str::<MonitorSpecifier>(r#"["re:("]"#).is_err());
+    }
+}
@@ -1,38 +1,138 @@
 #![allow(dead_code, unused_variables)]
-use knus::Decode;
+use std::{cell::RefCell, rc::Rc};
+
+use knus::{Decode, DecodeScalar};
 use schemars::JsonSchema;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
+pub mod action;
 pub mod common;
 pub mod shared;
+pub(crate) mod template;
+pub(crate) mod theme;
+pub mod unknown;
 mod util;
+pub mod when;
 pub mod widgets;
 
-#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
 pub struct Root {
+    /// other config files to load, relative to this file.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    #[serde(default)]
     pub widgets: Vec<WidgetConf>,
 }
 
-impl<S: knus::traits::ErrorSpan> knus::DecodeChildren<S> for Root {
+const ROOT_NODES: &[&str] = &[
+    "btn",
+    "slider",
+    "workspace",
+    "wrap-box",
+    "include",
+    "theme",
+    "template",
+    "defaults",
+    "strict",
+];
+
+/// theme, templates, defaults and strictness of one load, shared by every file of it.
+/// kept in the decode context, documents decoded on their own get a fresh one.
+#[derive(Default)]
+pub struct LoadContext {
+    pub(crate) theme: theme::Theme,
+    pub(crate) templates: template::Templates,
+    /// unknown nodes are errors instead of warnings.
+    pub strict: bool,
+    /// byte offset of unknown nodes and the message.
+    pub warnings: Vec<(usize, String)>,
+}
+impl LoadContext {
+    pub(crate) fn of<S: knus::traits::ErrorSpan>(
+        ctx: &mut knus::decode::Context<S>,
+    ) -> Rc<RefCell<Self>> {
+        if let Some(load) = ctx.get::<Rc<RefCell<Self>>>() {
+            return load.clone();
+        }
+        let load = Rc::<RefCell<Self>>::default();
+        ctx.set(load.clone());
+        load
+    }
+}
+
+impl<S: knus::traits::ErrorSpan + 'static> knus::DecodeChildren<S> for Root {
     fn decode_children(
         nodes: &[knus::ast::SpannedNode<S>],
         ctx: &mut knus::decode::Context<S>,
     ) -> Result<Self, knus::errors::DecodeError<S>> {
+        let load = LoadContext::of(ctx);
+
+        // theme and templates first, widgets can use them wherever they're declared
+        for n in nodes {
+            match n.node_name.as_ref() {
+                "theme" => theme::decode_theme(n, &mut load.borrow_mut().theme)?,
+                "template" => template::define_template(n, ctx, &mut load.borrow_mut().templates)?,
+                "defaults" => template::define_defaults(n, ctx, &mut load.borrow_mut().templates)?,
+                "strict" => load.borrow_mut().strict = true,
+                _ => {}
+            }
+        }
+
+        let mut include = vec![];
         let mut widgets = vec![];
         for n in nodes {
             match n.node_name.as_ref() {
                 "btn" | "slider" | "workspace" | "wrap-box" => {
-                    widgets.push(WidgetConf::decode_node(n, ctx)?);
+                    let mut n = n.clone();
+                    let res = {
+                        let load = load.borrow();
+                        template::apply_templates(&mut n, &load.templates)
+                            .map(|_| template::apply_defaults(&mut n, &load.templates))
+                            .and_then(|_| theme::substitute_node(&mut n, &load.theme))
+                    }
+                    .and_then(|_| WidgetConf::decode_node(&n, ctx));
+                    // keep going, errors of every widget are reported together
+                    match res {
+                        Ok(w) => widgets.push(w),
+                        Err(e) => ctx.emit_error(e),
+                    }
                 }
-                _ => {}
+                "include" => {
+                    self::util::argv(n)?;
+                    for arg in n.arguments.iter() {
+                        include.push(String::decode(arg, ctx)?);
+                    }
+                }
+                "theme" | "template" | "defaults" | "strict" => {}
+                _ => unknown::unknown_node(n, ROOT_NODES, ctx),
             }
         }
 
-        Ok(Self { widgets })
+        Ok(Self { include, widgets })
     }
 }
 
-#[derive(Debug, Clone, Decode, Deserialize, JsonSchema)]
+/// `include` nodes of a kdl document, read before anything else of a load is decoded.
+pub(crate) struct Includes(pub Vec<String>);
+
+impl<S: knus::traits::ErrorSpan> knus::DecodeChildren<S> for Includes {
+    fn decode_children(
+        nodes: &[knus::ast::SpannedNode<S>],
+        ctx: &mut knus::decode::Context<S>,
+    ) -> Result<Self, knus::errors::DecodeError<S>> {
+        let mut include = vec![];
+        for n in nodes.iter().filter(|n| n.node_name.as_ref() == "include") {
+            self::util::argv(n)?;
+            for arg in n.arguments.iter() {
+                include.push(String::decode(arg, ctx)?);
+            }
+        }
+        Ok(Self(include))
+    }
+}
+
+#[derive(Debug, Clone, Decode, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "kebab-case", tag = "type")]
 pub enum WidgetConf {
     Btn(Btn),
@@ -49,11 +149,47 @@ impl WidgetConf {
             WidgetConf::WrapBox(c) => &c.common,
         }
     }
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            WidgetConf::Btn(_) => "btn",
+            WidgetConf::Slider(_) => "slider",
+            WidgetConf::Workspace(_) => "workspace",
+            WidgetConf::WrapBox(_) => "wrap-box",
+        }
+    }
+    /// whether two configs would build the same widget, configs hold maps and sets without
+    /// an order of their own, the serialized values are compared instead.
+    pub fn same_as(&self, other: &Self) -> bool {
+        serde_json::to_value(self).ok() == serde_json::to_value(other).ok()
+    }
+    /// event maps of the widget and its items.
+    pub fn event_maps(&self) -> Vec<&shared::KeyEventMap> {
+        use widgets::{slide::preset::Preset, wrapbox::BoxedWidget};
+        match self {
+            WidgetConf::Btn(c) => vec![&c.widget.event_map],
+            WidgetConf::Slider(c) => match &c.widget.preset {
+                Preset::Custom(p) => vec![&p.event_map],
+                Preset::Ipc(p) => vec![&p.event_map],
+                _ => vec![],
+            },
+            WidgetConf::Workspace(_) => vec![],
+            WidgetConf::WrapBox(c) => c
+                .widget
+                .items
+                .iter()
+                .filter_map(|item| match &item.widget {
+                    BoxedWidget::Ring(r) => Some(&r.event_map),
+                    BoxedWidget::Text(t) => Some(&t.event_map),
+                    BoxedWidget::Tray(_) => None,
+                })
+                .collect(),
+        }
+    }
 }
 
 macro_rules! impl_top_level_widget {
     ($name:ident, $config:ty) => {
-        #[derive(Debug, Clone, Deserialize, JsonSchema)]
+        #[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
         pub struct $name {
             #[serde(flatten)]
             pub common: common::CommonConfig,
@@ -65,10 +201,16 @@ macro_rules! impl_top_level_widget {
                 node: &knus::ast::SpannedNode<S>,
                 ctx: &mut knus::decode::Context<S>,
             ) -> Result<Self, knus::errors::DecodeError<S>> {
-                Ok(Self {
-                    common: common::CommonConfig::decode_node(node, ctx)?,
-                    widget: <$config>::decode_node(node, ctx)?,
-                })
+                let conf = Self {
+                    common: common::CommonConfig::decode_known(node, ctx)?,
+                    widget: <$config>::decode_known(node, ctx)?,
+                };
+                unknown::check_children(
+                    node,
+                    &[common::CommonConfig::KDL_CHILDREN, <$config>::KDL_CHILDREN],
+                    ctx,
+                );
+                Ok(conf)
             }
         }
     };
@@ -79,16 +221,271 @@ impl_top_level_widget!(Slide, widgets::slide::base::SlideConfig);
 impl_top_level_widget!(Workspace, widgets::workspace::WorkspaceConfig);
 impl_top_level_widget!(WrapBox, widgets::wrapbox::BoxConfig);
 
-pub fn parse_kdl(s: &str) -> Result<Root, String> {
-    match knus::parse::<Root>("config.kdl", s) {
-        Ok(config) => Ok(config),
-        Err(e) => {
-            let msg = format!("Failed to parse kdl config: {}", e);
-            println!("{:?}", miette::Report::new(e));
-            Err(msg)
+/// decode a kdl document within `load`, unknown nodes are left in its warnings.
+pub fn parse_kdl(
+    file_name: &str,
+    s: &str,
+    load: &Rc<RefCell<LoadContext>>,
+) -> Result<Root, knus::Error> {
+    knus::parse_with_context::<Root, knus::span::Span, _>(file_name, s, |ctx| ctx.set(load.clone()))
+}
+
+/// a json document on its own.
+pub fn parse_jsonc(s: &str) -> Result<Root, String> {
+    let mut load = LoadContext::default();
+    let value = define_json(s, &mut load)?;
+    decode_json(s, value, &load)
+}
+
+/// parse a json document and take its theme, templates and defaults into `load`.
+pub fn define_json(s: &str, load: &mut LoadContext) -> Result<serde_jsonrc::Value, String> {
+    let mut value: serde_jsonrc::Value =
+        serde_jsonrc::from_str(s).map_err(|e| format!("JSON parse error: {e}"))?;
+    if let Some(t) = value.as_object_mut().and_then(|o| o.remove("theme")) {
+        theme::decode_json_theme(&t, &mut load.theme)?;
+    }
+    if let Some(t) = value.as_object_mut().and_then(|o| o.remove("templates")) {
+        template::define_json_templates(t, &mut load.templates)?;
+    }
+    if let Some(d) = value.as_object_mut().and_then(|o| o.remove("defaults")) {
+        template::define_json_defaults(d, &mut load.templates)?;
+    }
+    Ok(value)
+}
+
+/// decode the widgets of a json document, after `define_json` of every file of the load.
+pub fn decode_json(
+    s: &str,
+    mut value: serde_jsonrc::Value,
+    load: &LoadContext,
+) -> Result<Root, String> {
+    let mut changed = false;
+    if let Some(widgets) = value.get_mut("widgets").and_then(|w| w.as_array_mut()) {
+        for w in widgets.iter_mut() {
+            changed |= template::apply_json_templates(w, &load.templates)?;
+            changed |= template::apply_json_defaults(w, &load.templates);
         }
     }
+    changed |= theme::substitute_json(&mut value, &load.theme)?;
+    if changed {
+        serde_jsonrc::from_value(value).map_err(|e| format!("JSON parse error: {e}"))
+    } else {
+        // parse the text again, errors come with line numbers
+        serde_jsonrc::from_str(s).map_err(|e| format!("JSON parse error: {e}"))
+    }
 }
-pub fn parse_jsonc(s: &str) -> Result<Root, String> {
-    serde_jsonrc::from_str(s).map_err(|e| format!("JSON parse error: {e}"))
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_root_with_include() {
+        let kdl = r#"
+include "widgets/audio.kdl" "widgets/workspace.jsonc"
+btn {
+    edge "bottom"
+    thickness 20
+    length "40%"
+}
+"#;
+        let root: Root = knus::parse("test", kdl).unwrap();
+        assert_eq!(
+            root.include,
+            vec!["widgets/audio.kdl", "widgets/workspace.jsonc"]
+        );
+        assert_eq!(root.widgets.len(), 1);
+    }
+
+    #[test]
+    fn test_decode_root_empty_include() {
+        let kdl = r#"include"#;
+        assert!(knus::parse::<Root>("test", kdl).is_err());
+    }
+
+    // both formats go through the same derived config, compared by value like on reload
+    fn assert_same_root(kdl: &str, json: &str) {
+        let value = |root: &Root| serde_json::to_value(root).unwrap();
+        let from_kdl: Root = knus::parse("test", kdl).unwrap();
+        let from_json = parse_jsonc(json).unwrap();
+        assert_eq!(value(&from_kdl), value(&from_json));
+
+        // kdl -> Root -> json -> Root
+        let written = serde_json::to_string_pretty(&from_kdl).unwrap();
+        let again = parse_jsonc(&written).unwrap();
+        assert_eq!(value(&again), value(&from_kdl), "{written}");
+    }
+
+    #[test]
+    fn test_round_trip_defaults() {
+        assert_same_root(
+            r#"
+btn {
+    edge "bottom"
+    thickness 20
+    length "40%"
+}
+slider {
+    edge "right"
+    thickness 20
+    length "30%"
+}
+workspace {
+    edge "top"
+    thickness 10
+    length "50%"
+}
+"#,
+            r#"{
+    "widgets": [
+        { "type": "btn", "edge": "bottom", "thickness": 20, "length": "40%" },
+        { "type": "slider", "edge": "right", "thickness": 20, "length": "30%" },
+        { "type": "workspace", "edge": "top", "thickness": 10, "length": "50%" }
+    ]
+}"#,
+        );
+    }
+
+    #[test]
+    fn test_round_trip_fields() {
+        assert_same_root(
+            r##"
+btn {
+    edge "bottom"
+    namespace "launcher"
+    thickness 20
+    length "40%"
+    color "#ff0000"
+    border-width 5
+    event-map {
+        mouse-left "nwg-drawer"
+    }
+}
+slider {
+    edge "right"
+    namespace "volume"
+    thickness 20
+    length "30%"
+    fg-color "#FFB847"
+    fg-text-color "#000000"
+    scroll-unit 0.01
+    redraw-only-on-internal-update
+    preset "speaker" {
+        mute-color "#333333"
+        animation-curve "ease-quad"
+        device "alsa_output"
+    }
+}
+slider {
+    edge "right"
+    namespace "progress"
+    thickness 20
+    length "30%"
+    redraw-only-on-internal-update false
+    preset "ipc" {
+        initial-value 0.5
+        pop-on-set
+        on-change-command "notify-send {float:2,100}%"
+    }
+}
+workspace {
+    edge "top"
+    thickness 10
+    length "50%"
+    hover-color "#ffffff"
+    focused-only
+    border-width 2
+    preset "niri"
+}
+"##,
+            r##"{
+    "widgets": [
+        {
+            "type": "btn",
+            "edge": "bottom",
+            "namespace": "launcher",
+            "thickness": 20,
+            "length": "40%",
+            "color": "#ff0000",
+            "border-width": 5,
+            "event-map": { "mouse-left": "nwg-drawer" }
+        },
+        {
+            "type": "slider",
+            "edge": "right",
+            "namespace": "volume",
+            "thickness": 20,
+            "length": "30%",
+            "fg-color": "#FFB847",
+            "fg-text-color": "#000000",
+            "scroll-unit": 0.01,
+            "redraw-only-on-internal-update": true,
+            "preset": {
+                "type": "speaker",
+                "mute-color": "#333333",
+                "animation-curve": "ease-quad",
+                "device": "alsa_output"
+            }
+        },
+        {
+            "type": "slider",
+            "edge": "right",
+            "namespace": "progress",
+            "thickness": 20,
+            "length": "30%",
+            "redraw-only-on-internal-update": false,
+            "preset": {
+                "type": "ipc",
+                "initial-value": 0.5,
+                "pop-on-set": true,
+                "on-change-command": "notify-send {float:2,100}%"
+            }
+        },
+        {
+            "type": "workspace",
+            "edge": "top",
+            "thickness": 10,
+            "length": "50%",
+            "hover-color": "#ffffff",
+            "focused-only": true,
+            "border-width": 2,
+            "preset": "niri"
+        }
+    ]
+}"##,
+        );
+    }
+
+    #[test]
+    fn test_required_fields() {
+        let kdl = r#"btn { edge "bottom"; thickness 20; }"#;
+        assert!(knus::parse::<Root>("test", kdl).is_err());
+        let json = r#"{ "widgets": [{ "type": "btn", "edge": "bottom", "thickness": 20 }] }"#;
+        assert!(parse_jsonc(json).is_err());
+    }
+
+    #[test]
+    fn test_same_as() {
+        let conf = |monitor: &str, color: &str| {
+            let kdl = format!(
+                r##"btn {{
+    edge "bottom"
+    thickness 20
+    length "40%"
+    monitor {monitor}
+    color "{color}"
+    event-map {{
+        mouse-left "a"
+        mouse-right "b"
+        shift+mouse-left "c"
+    }}
+}}"##
+            );
+            knus::parse::<Root>("test", &kdl).unwrap().widgets.remove(0)
+        };
+        let a = conf(r#""DP-1" "HDMI-A-1" "eDP-1""#, "#ff0000");
+        assert!(a.same_as(&conf(r#""eDP-1" "DP-1" "HDMI-A-1""#, "#ff0000")));
+        assert!(!a.same_as(&conf(r#""DP-1" "HDMI-A-1" "eDP-1""#, "#00ff00")));
+        assert!(!a.same_as(&conf(r#""DP-1""#, "#ff0000")));
+    }
 }
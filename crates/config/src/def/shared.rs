@@ -2,7 +2,7 @@ use cosmic_text::{Color, FamilyOwned};
 use knus::{errors::DecodeError, Decode, DecodeScalar};
 use regex_lite::Regex;
 use schemars::{json_schema, JsonSchema};
-use serde::{Deserialize, Deserializer};
+use serde::{ser::SerializeMap, Deserialize, Deserializer, Serialize, Serializer};
 use serde_jsonrc::Value;
 use smithay_client_toolkit::shell::wlr_layer::Anchor;
 use std::collections::HashMap;
@@ -10,8 +10,11 @@ use std::convert::Infallible;
 use std::ops::Deref;
 use std::str::FromStr;
 use string_to_num::ParseNum;
-use util::color::parse_color;
-use util::shell::shell_cmd_non_block;
+use util::color::{color_to_hex, parse_color};
+use util::template::base::Template;
+use way_edges_derive::Config;
+
+use super::action::{run_actions, Action, ACTION_NAMES};
 
 #[rustfmt::skip]
 static ACTION_CODE_PAIRS: &[(&str, u32)] = &[
@@ -22,14 +25,40 @@ static ACTION_CODE_PAIRS: &[(&str, u32)] = &[
     ("mouse-extra",   0x114),
     ("mouse-forward", 0x115),
     ("mouse-back",    0x116),
+    ("scroll-up",     SCROLL_UP),
+    ("scroll-down",   SCROLL_DOWN),
+    ("scroll-left",   SCROLL_LEFT),
+    ("scroll-right",  SCROLL_RIGHT),
+    ("on-enter",      ON_ENTER),
+    ("on-leave",      ON_LEAVE),
 ];
 
-#[derive(Debug, Clone, Copy, Default, DecodeScalar, PartialEq, Deserialize, JsonSchema)]
+// not key codes, for the event map keys that aren't mouse buttons
+pub const SCROLL_UP: u32 = 0x10000;
+pub const SCROLL_DOWN: u32 = 0x10001;
+pub const SCROLL_LEFT: u32 = 0x10002;
+pub const SCROLL_RIGHT: u32 = 0x10003;
+pub const ON_ENTER: u32 = 0x10004;
+pub const ON_LEAVE: u32 = 0x10005;
+
+/// scrolled distance it takes to run a scroll binding, one wheel step is 10 or 15
+/// on most compositors, touchpads send many small ones.
+const DEFAULT_SCROLL_THRESHOLD: f64 = 10.;
+
+#[derive(
+    Debug, Clone, Copy, Default, DecodeScalar, PartialEq, Deserialize, Serialize, JsonSchema,
+)]
+#[serde(rename_all = "kebab-case")]
 pub enum Curve {
+    // json used to take the variant names
+    #[serde(alias = "Linear")]
     Linear,
+    #[serde(alias = "EaseQuad")]
     EaseQuad,
     #[default]
+    #[serde(alias = "EaseCubic")]
     EaseCubic,
+    #[serde(alias = "EaseExpo")]
     EaseExpo,
 }
 
@@ -240,12 +269,31 @@ impl<'de> Deserialize<'de> for NumOrRelative {
         d.deserialize_any(F64OrRelativeVisitor)
     }
 }
+impl Serialize for NumOrRelative {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            // `20` instead of `20.0`
+            Self::Num(n) if n.fract() == 0. && n.abs() < i64::MAX as f64 => {
+                s.serialize_i64(n as i64)
+            }
+            Self::Num(n) => s.serialize_f64(n),
+            Self::Relative(r) => s.serialize_str(&format!("{}%", to_percentage(r))),
+        }
+    }
+}
 
-#[derive(Debug, Clone, Default, Decode, PartialEq, Deserialize, JsonSchema)]
+// the shortest percentage parsed back to exactly `r`, `0.07 * 100.` is not `7`
+fn to_percentage(r: f64) -> String {
+    let p = r * 100.;
+    (0..17)
+        .map(|digits| format!("{p:.digits$}"))
+        .find(|s| f64::from_str(s).is_ok_and(|v| v * 0.01 == r))
+        .unwrap_or_else(|| p.to_string())
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Config)]
 pub struct CommonSize {
-    #[knus(child, unwrap(argument))]
     pub thickness: NumOrRelative,
-    #[knus(child, unwrap(argument))]
     pub length: NumOrRelative,
 }
 impl CommonSize {
@@ -260,21 +308,246 @@ impl CommonSize {
     }
 }
 
+/// `mouse-left` like names or `kc-<number>`.
+pub fn parse_key_code(key: &str) -> Result<u32, String> {
+    if let Some(key_code_str) = key.strip_prefix("kc-") {
+        key_code_str
+            .parse_num::<u32>()
+            .map_err(|_| format!("Invalid key code after 'kc-' prefix: '{key_code_str}'"))
+    } else {
+        ACTION_CODE_PAIRS
+            .iter()
+            .find_map(|&(k, code)| (k == key).then_some(code))
+            .ok_or_else(|| {
+                format!("Unknown action key: '{key}', expected 'kc-<number>' or predefined action")
+            })
+    }
+}
+
+static MODIFIER_NAMES: &[&str] = &["shift", "ctrl", "alt", "super"];
+
+/// how the key was clicked, only known on release.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Gesture {
+    #[default]
+    Click,
+    Double,
+    Long,
+}
+
+/// keyboard modifiers held while clicking.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+    pub logo: bool,
+}
+impl Modifiers {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// key of an event map, like `shift+double-mouse-left`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyBinding {
+    pub code: u32,
+    pub gesture: Gesture,
+    pub modifiers: Modifiers,
+}
+impl From<u32> for KeyBinding {
+    fn from(code: u32) -> Self {
+        Self {
+            code,
+            gesture: Gesture::Click,
+            modifiers: Modifiers::default(),
+        }
+    }
+}
+impl KeyBinding {
+    fn is_plain(&self) -> bool {
+        self.gesture == Gesture::Click && self.modifiers.is_empty()
+    }
+}
+impl std::fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let m = self.modifiers;
+        for (held, name) in [m.shift, m.ctrl, m.alt, m.logo].iter().zip(MODIFIER_NAMES) {
+            if *held {
+                write!(f, "{name}+")?;
+            }
+        }
+        match self.gesture {
+            Gesture::Click => {}
+            Gesture::Double => f.write_str("double-")?,
+            Gesture::Long => f.write_str("long-")?,
+        }
+        match ACTION_CODE_PAIRS
+            .iter()
+            .find(|&&(_, code)| code == self.code)
+        {
+            Some((name, _)) => f.write_str(name),
+            None => write!(f, "kc-{}", self.code),
+        }
+    }
+}
+
+/// `[<modifier>+]...[double-|long-]<key>`, the key is what [`parse_key_code`] takes.
+pub fn parse_key_binding(key: &str) -> Result<KeyBinding, String> {
+    let mut parts = key.split('+');
+    let last = parts.next_back().unwrap_or_default();
+
+    let mut modifiers = Modifiers::default();
+    for m in parts {
+        let held = match m {
+            "shift" => &mut modifiers.shift,
+            "ctrl" => &mut modifiers.ctrl,
+            "alt" => &mut modifiers.alt,
+            "super" => &mut modifiers.logo,
+            _ => {
+                return Err(format!(
+                    "Unknown modifier '{m}' in '{key}', expected one of: {}",
+                    MODIFIER_NAMES.join(", ")
+                ))
+            }
+        };
+        *held = true;
+    }
+
+    let (gesture, last) = if let Some(k) = last.strip_prefix("double-") {
+        (Gesture::Double, k)
+    } else if let Some(k) = last.strip_prefix("long-") {
+        (Gesture::Long, k)
+    } else {
+        (Gesture::Click, last)
+    };
+
+    let code = parse_key_code(last)?;
+    if code >= SCROLL_UP && (gesture != Gesture::Click || !modifiers.is_empty()) {
+        return Err(format!(
+            "'double-', 'long-' and modifiers only work with mouse buttons, not '{last}'"
+        ));
+    }
+
+    Ok(KeyBinding {
+        code,
+        gesture,
+        modifiers,
+    })
+}
+
 #[derive(Debug, Default, Clone)]
-pub struct KeyEventMap(HashMap<u32, String>);
+pub struct KeyEventMap {
+    bindings: HashMap<KeyBinding, Vec<Action>>,
+    scroll_thresholds: HashMap<u32, f64>,
+    // horizontal and vertical distance scrolled since the last scroll binding ran
+    scrolled: [f64; 2],
+}
 impl KeyEventMap {
-    pub fn call(&self, k: u32) {
-        if let Some(cmd) = self.0.get(&k) {
-            // PERF: SHOULE THIS BE USE OF CLONING???
-            shell_cmd_non_block(cmd.clone());
+    /// what is bound to exactly `k`, without falling back to other bindings.
+    pub fn get_actions(&self, k: &KeyBinding) -> Option<&[Action]> {
+        self.bindings.get(k).map(Vec::as_slice)
+    }
+
+    pub fn len(&self) -> usize {
+        self.bindings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bindings.is_empty()
+    }
+
+    fn is_bound(&self, k: &KeyBinding) -> bool {
+        self.bindings.contains_key(k)
+    }
+
+    // run what is bound to exactly `k`
+    fn run(&self, k: &KeyBinding) {
+        if let Some(actions) = self.bindings.get(k) {
+            run_actions(actions);
         }
     }
-}
-impl Deref for KeyEventMap {
-    type Target = HashMap<u32, String>;
 
-    fn deref(&self) -> &Self::Target {
-        &self.0
+    // modifiers matter more than the gesture: `shift+double-mouse-left` falls back to
+    // `shift+mouse-left`, then `double-mouse-left`, then `mouse-left`.
+    fn lookup(&self, k: KeyBinding) -> Option<KeyBinding> {
+        let click = KeyBinding {
+            gesture: Gesture::Click,
+            ..k
+        };
+        let no_modifiers = KeyBinding {
+            modifiers: Modifiers::default(),
+            ..k
+        };
+        [k, click, no_modifiers, KeyBinding::from(k.code)]
+            .into_iter()
+            .find(|k| self.is_bound(k))
+    }
+
+    pub fn call(&self, k: KeyBinding) {
+        if let Some(k) = self.lookup(k) {
+            self.run(&k);
+        }
+    }
+
+    /// run the command bound to `k` as if the key was released on the widget.
+    pub fn trigger(&self, k: KeyBinding) -> Result<(), String> {
+        if !self.is_bound(&k) {
+            return Err(format!("No action bound to {k}"));
+        }
+        self.run(&k);
+        Ok(())
+    }
+
+    /// the widget needs keyboard focus to know about modifiers.
+    pub fn uses_modifiers(&self) -> bool {
+        self.bindings.keys().any(|k| !k.modifiers.is_empty())
+    }
+
+    /// add up the distances of an axis event, the scroll binding of a direction runs
+    /// once its threshold is reached.
+    pub fn scroll(&mut self, horizontal: f64, vertical: f64) {
+        let h = self.accumulate(0, horizontal, [SCROLL_LEFT, SCROLL_RIGHT]);
+        let v = self.accumulate(1, vertical, [SCROLL_UP, SCROLL_DOWN]);
+        for code in h.into_iter().chain(v) {
+            self.call(KeyBinding::from(code));
+        }
+    }
+
+    fn accumulate(&mut self, axis: usize, delta: f64, [neg, pos]: [u32; 2]) -> Option<u32> {
+        if delta == 0. {
+            return None;
+        }
+        let scrolled = &mut self.scrolled[axis];
+        // turning around starts over
+        if *scrolled != 0. && scrolled.signum() != delta.signum() {
+            *scrolled = 0.;
+        }
+        *scrolled += delta;
+
+        let code = if *scrolled < 0. { neg } else { pos };
+        let threshold = self
+            .scroll_thresholds
+            .get(&code)
+            .copied()
+            .unwrap_or(DEFAULT_SCROLL_THRESHOLD);
+        if scrolled.abs() < threshold {
+            return None;
+        }
+        *scrolled = 0.;
+        Some(code)
+    }
+
+    fn set_scroll_threshold(&mut self, k: KeyBinding, threshold: f64) -> Result<(), String> {
+        if !(SCROLL_UP..=SCROLL_RIGHT).contains(&k.code) {
+            return Err(format!("threshold only works with scroll keys, not '{k}'"));
+        }
+        if threshold <= 0. {
+            return Err(format!("threshold must be positive, got {threshold}"));
+        }
+        self.scroll_thresholds.insert(k.code, threshold);
+        Ok(())
     }
 }
 impl<S: knus::traits::ErrorSpan> knus::Decode<S> for KeyEventMap {
@@ -282,53 +555,68 @@ impl<S: knus::traits::ErrorSpan> knus::Decode<S> for KeyEventMap {
         node: &knus::ast::SpannedNode<S>,
         ctx: &mut knus::decode::Context<S>,
     ) -> Result<Self, DecodeError<S>> {
-        let mut map: HashMap<u32, String> = HashMap::new();
+        let mut map = KeyEventMap::default();
 
         for child in node.children() {
-            let kc = if child.node_name.starts_with("kc-") {
-                // strip the "kc-" prefix
-                let key_code_str = &child.node_name[3..];
-                println!("key_code_str: {}", key_code_str);
-                if let Ok(key_code) = key_code_str.parse_num::<u32>() {
-                    key_code
-                } else {
-                    return Err(DecodeError::unsupported(
+            let kc = parse_key_binding(&child.node_name)
+                .map_err(|e| DecodeError::unsupported(&child.node_name, e))?;
+
+            let actions = match (child.arguments.first(), child.children.is_some()) {
+                (Some(arg), false) => {
+                    if let knus::ast::Literal::String(s) = arg.literal.deref() {
+                        vec![Action::Shell(s.to_string())]
+                    } else {
+                        return Err(DecodeError::unsupported(
+                            &arg.literal,
+                            "Expected a string literal for command",
+                        ));
+                    }
+                }
+                (None, true) => Action::decode_children(child)?,
+                (Some(_), true) => {
+                    return Err(DecodeError::unexpected(
                         &child.node_name,
-                        "Invalid key code after 'kc-' prefix",
-                    ));
+                        "command",
+                        "Expected either a command or a block of actions, not both",
+                    ))
+                }
+                (None, false) => {
+                    return Err(DecodeError::unexpected(
+                        &child.node_name,
+                        "command",
+                        "Expected at least one argument for command",
+                    ))
                 }
-            } else if let Some(kc) = ACTION_CODE_PAIRS
-                .iter()
-                .find_map(|&(k, code)| (k == child.node_name.as_ref()).then_some(code))
-            {
-                kc
-            } else {
-                return Err(DecodeError::unsupported(
-                    &child.node_name,
-                    "Unknown action key, expected 'kc_<number>' or predefined action",
-                ));
             };
 
-            let command = if let Some(arg) = child.arguments.first() {
-                if let knus::ast::Literal::String(s) = arg.literal.deref() {
-                    s.to_string()
-                } else {
-                    return Err(DecodeError::unsupported(
-                        &arg.literal,
-                        "Expected a string literal for command",
+            for (name, value) in child.properties.iter() {
+                if name.as_ref() != "threshold" {
+                    return Err(DecodeError::unexpected(
+                        name,
+                        "property",
+                        format!("unknown property `{}`, expected `threshold`", name.as_ref()),
                     ));
                 }
-            } else {
-                return Err(DecodeError::unexpected(
-                    &child.node_name,
-                    "command",
-                    "Expected at least one argument for command",
-                ));
-            };
+                let threshold = match value.literal.deref() {
+                    knus::ast::Literal::Decimal(d) => TryInto::<f64>::try_into(d)
+                        .map_err(|e| DecodeError::conversion(&value.literal, e))?,
+                    knus::ast::Literal::Int(i) => TryInto::<i64>::try_into(i)
+                        .map_err(|e| DecodeError::conversion(&value.literal, e))?
+                        as f64,
+                    _ => {
+                        return Err(DecodeError::unsupported(
+                            &value.literal,
+                            "Expected a number for threshold",
+                        ))
+                    }
+                };
+                map.set_scroll_threshold(kc, threshold)
+                    .map_err(|e| DecodeError::conversion(&value.literal, e))?;
+            }
 
-            map.insert(kc, command);
+            map.bindings.insert(kc, actions);
         }
-        Ok(KeyEventMap(map))
+        Ok(map)
     }
 }
 
@@ -342,47 +630,80 @@ impl<'de> Deserialize<'de> for KeyEventMap {
             type Value = KeyEventMap;
 
             fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-                formatter.write_str("vec of tuples: (key: number, command: string)")
+                formatter
+                    .write_str("map of key to command, or to {command, threshold} for scroll keys")
             }
 
             fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
             where
                 A: serde::de::MapAccess<'a>,
             {
-                let mut event_map = HashMap::new();
-                while let Some((key, value)) = map.next_entry::<String, String>()? {
-                    let kc = if let Some(key_code_str) = key.strip_prefix("kc-") {
-                        // strip the "kc-" prefix
-                        println!("key_code_str: {}", key_code_str);
-                        if let Ok(key_code) = key_code_str.parse_num::<u32>() {
-                            key_code
-                        } else {
-                            return Err(serde::de::Error::custom(format!(
-                                "Invalid key code after 'kc-' prefix: '{}'",
-                                key_code_str
-                            )));
+                let mut event_map = KeyEventMap::default();
+                while let Some((key, value)) = map.next_entry::<String, Value>()? {
+                    let kc = parse_key_binding(&key).map_err(serde::de::Error::custom)?;
+
+                    let command = match value {
+                        Value::Object(mut obj) => {
+                            if let Some(t) = obj.remove("threshold") {
+                                let t = t.as_f64().ok_or_else(|| {
+                                    serde::de::Error::custom("threshold must be a number")
+                                })?;
+                                event_map
+                                    .set_scroll_threshold(kc, t)
+                                    .map_err(serde::de::Error::custom)?;
+                            }
+                            match obj.remove("command") {
+                                Some(command) if obj.is_empty() => command,
+                                _ => {
+                                    return Err(serde::de::Error::custom(format!(
+                                        "'{key}' expects {{\"command\": string, \"threshold\": number}}"
+                                    )))
+                                }
+                            }
                         }
-                    } else if let Some(kc) = ACTION_CODE_PAIRS
-                        .iter()
-                        .find_map(|&(k, code)| (k == key).then_some(code))
-                    {
-                        kc
-                    } else {
-                        return Err(serde::de::Error::custom(format!(
-                            "Unknown action key: '{}'.",
-                            key
-                        )));
+                        v => v,
                     };
-
-                    event_map.insert(kc, value);
+                    let actions = Action::from_json(command).map_err(serde::de::Error::custom)?;
+                    event_map.bindings.insert(kc, actions);
                 }
-                Ok(KeyEventMap(event_map))
+                Ok(event_map)
             }
         }
         deserializer.deserialize_any(EventMapVisitor)
     }
 }
 
+// same shape as deserialized, `{command, threshold}` only for scroll keys with a threshold
+impl Serialize for KeyEventMap {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let mut entries = self
+            .bindings
+            .iter()
+            .map(|(&k, actions)| {
+                let command = match actions.as_slice() {
+                    [Action::Shell(cmd)] => Value::String(cmd.clone()),
+                    _ => Action::to_json(actions),
+                };
+                (k, command)
+            })
+            .collect::<Vec<_>>();
+        // the map has no order of its own
+        entries.sort_by_key(|(k, _)| (k.code, k.to_string()));
+
+        let mut map = s.serialize_map(Some(entries.len()))?;
+        for (k, command) in entries {
+            match self.scroll_thresholds.get(&k.code) {
+                Some(&threshold) if k.is_plain() => map.serialize_entry(
+                    &k.to_string(),
+                    &serde_jsonrc::json!({ "command": command, "threshold": threshold }),
+                )?,
+                _ => map.serialize_entry(&k.to_string(), &command)?,
+            }
+        }
+        map.end()
+    }
+}
+
 impl JsonSchema for KeyEventMap {
     fn schema_id() -> std::borrow::Cow<'static, str> {
         Self::schema_name()
@@ -393,14 +714,57 @@ impl JsonSchema for KeyEventMap {
     }
 
     fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
-        let allowed_str_keys: Vec<_> = ACTION_CODE_PAIRS.iter().map(|&(k, _)| k).collect();
-        let str_keys_pattern = format!("^({})$", allowed_str_keys.join("|"));
+        // scrolling and hovering can't have gestures or modifiers
+        let buttons: Vec<_> = ACTION_CODE_PAIRS
+            .iter()
+            .map(|&(k, _)| k)
+            .filter(|k| k.starts_with("mouse-"))
+            .collect();
+        let button_pattern = format!(
+            r"^(({})\+)*((double|long)-)?({}|kc-\w+)$",
+            MODIFIER_NAMES.join("|"),
+            buttons.join("|")
+        );
+        // a shell command, or a list of shell commands and `["action", args...]`
+        let command = json_schema!({
+            "anyOf": [
+                {"type": "string"},
+                {
+                    "type": "array",
+                    "items": {
+                        "anyOf": [
+                            {"type": "string"},
+                            {
+                                "type": "array",
+                                "prefixItems": [{"enum": ACTION_NAMES}],
+                                "items": {"type": ["string", "number"]},
+                                "minItems": 1
+                            }
+                        ]
+                    }
+                }
+            ]
+        });
 
         json_schema!({
             "type": "object",
             "patternProperties": {
-                r"^\d+$": {"type": "string"},
-                str_keys_pattern: {"type": "string"}
+                button_pattern: command,
+                "^on-(enter|leave)$": command,
+                "^scroll-(up|down|left|right)$": {
+                    "anyOf": [
+                        command,
+                        {
+                            "type": "object",
+                            "properties": {
+                                "command": command,
+                                "threshold": {"type": "number", "exclusiveMinimum": 0}
+                            },
+                            "required": ["command"],
+                            "additionalProperties": false
+                        }
+                    ]
+                }
             },
             "additionalProperties": false
         })
@@ -422,9 +786,18 @@ mod tests {
 
         let key_event_map: KeyEventMap = serde_jsonrc::from_value(json_data).unwrap();
 
-        assert_eq!(key_event_map.0.get(&0x110).unwrap(), "command1");
-        assert_eq!(key_event_map.0.get(&0x112).unwrap(), "command2");
-        assert_eq!(key_event_map.0.get(&0x111).unwrap(), "command3");
+        assert_eq!(
+            key_event_map.get_actions(&KeyBinding::from(0x110)),
+            Some(&[Action::Shell("command1".into())][..])
+        );
+        assert_eq!(
+            key_event_map.get_actions(&KeyBinding::from(0x112)),
+            Some(&[Action::Shell("command2".into())][..])
+        );
+        assert_eq!(
+            key_event_map.get_actions(&KeyBinding::from(0x111)),
+            Some(&[Action::Shell("command3".into())][..])
+        );
     }
 
     #[test]
@@ -450,6 +823,151 @@ mod tests {
         // Expecting an error because the value is not a string
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_parse_key_code() {
+        assert_eq!(parse_key_code("mouse-left").unwrap(), 0x110);
+        assert_eq!(parse_key_code("kc-275").unwrap(), 275);
+        assert_eq!(parse_key_code("kc-0x113").unwrap(), 0x113);
+        assert!(parse_key_code("kc-abc").is_err());
+        assert!(parse_key_code("mouse-unknown").is_err());
+    }
+
+    #[test]
+    fn test_parse_key_binding() {
+        let k = parse_key_binding("shift+ctrl+double-mouse-right").unwrap();
+        assert_eq!(k.code, 0x111);
+        assert_eq!(k.gesture, Gesture::Double);
+        assert!(k.modifiers.shift && k.modifiers.ctrl && !k.modifiers.alt);
+        assert_eq!(k.to_string(), "shift+ctrl+double-mouse-right");
+
+        let k = parse_key_binding("long-kc-275").unwrap();
+        assert_eq!((k.code, k.gesture), (275, Gesture::Long));
+        assert!(k.modifiers.is_empty());
+
+        assert_eq!(
+            parse_key_binding("mouse-left").unwrap(),
+            KeyBinding::from(0x110)
+        );
+        assert!(parse_key_binding("hyper+mouse-left").is_err());
+        assert!(parse_key_binding("triple-mouse-left").is_err());
+    }
+
+    #[test]
+    fn test_curve_names() {
+        for (name, curve) in [
+            ("ease-expo", Curve::EaseExpo),
+            ("EaseExpo", Curve::EaseExpo),
+            ("EaseQuad", Curve::EaseQuad),
+            ("Linear", Curve::Linear),
+        ] {
+            assert_eq!(
+                serde_jsonrc::from_value::<Curve>(json!(name)).unwrap(),
+                curve
+            );
+        }
+        assert_eq!(
+            serde_jsonrc::to_value(Curve::EaseExpo).unwrap(),
+            json!("ease-expo")
+        );
+    }
+
+    #[test]
+    fn test_key_event_map_fallback() {
+        let json_data = json!({
+            "mouse-left": "click",
+            "double-mouse-left": "double",
+            "shift+mouse-left": "shift",
+        });
+        let map: KeyEventMap = serde_jsonrc::from_value(json_data).unwrap();
+        let lookup = |k: &str| {
+            map.lookup(parse_key_binding(k).unwrap())
+                .and_then(|k| map.get_actions(&k))
+                .map(<[Action]>::to_vec)
+        };
+        let shell = |cmd: &str| Some(vec![Action::Shell(cmd.to_string())]);
+
+        assert_eq!(map.len(), 3);
+        assert!(map.uses_modifiers());
+        assert_eq!(lookup("mouse-left"), shell("click"));
+        assert_eq!(lookup("long-mouse-left"), shell("click"));
+        assert_eq!(lookup("double-mouse-left"), shell("double"));
+        assert_eq!(lookup("shift+double-mouse-left"), shell("shift"));
+        assert_eq!(lookup("ctrl+double-mouse-left"), shell("double"));
+        assert_eq!(lookup("mouse-right"), None);
+    }
+
+    #[test]
+    fn test_scroll_threshold() {
+        let json_data = json!({
+            "scroll-up": {"command": "up", "threshold": 30},
+            "scroll-down": "down",
+            "on-enter": "preview",
+        });
+        let mut map: KeyEventMap = serde_jsonrc::from_value(json_data).unwrap();
+        assert_eq!(
+            map.get_actions(&ON_ENTER.into()).unwrap(),
+            [Action::Shell("preview".to_string())]
+        );
+
+        // touchpad
+        assert_eq!(map.accumulate(1, -12., [SCROLL_UP, SCROLL_DOWN]), None);
+        assert_eq!(map.accumulate(1, -12., [SCROLL_UP, SCROLL_DOWN]), None);
+        assert_eq!(
+            map.accumulate(1, -12., [SCROLL_UP, SCROLL_DOWN]),
+            Some(SCROLL_UP)
+        );
+        // turning around, default threshold
+        assert_eq!(map.accumulate(1, -5., [SCROLL_UP, SCROLL_DOWN]), None);
+        assert_eq!(
+            map.accumulate(1, 15., [SCROLL_UP, SCROLL_DOWN]),
+            Some(SCROLL_DOWN)
+        );
+
+        assert!(parse_key_binding("double-scroll-up").is_err());
+        assert!(parse_key_binding("shift+on-enter").is_err());
+        assert!(serde_jsonrc::from_value::<KeyEventMap>(
+            json!({"mouse-left": {"command": "a", "threshold": 3}})
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_key_event_map_actions() {
+        let json_data = json!({
+            "mouse-left": [["toggle-pin", "audio"], "notify-send pinned"],
+            "mouse-right": ["notify-send single"],
+            "scroll-up": {"command": [["set", "mic", 0]], "threshold": 30},
+        });
+        let map: KeyEventMap = serde_jsonrc::from_value(json_data).unwrap();
+
+        assert_eq!(
+            map.get_actions(&KeyBinding::from(0x110)).unwrap(),
+            [
+                Action::TogglePin("audio".to_string()),
+                Action::Shell("notify-send pinned".to_string())
+            ]
+        );
+        assert_eq!(
+            map.get_actions(&KeyBinding::from(0x111)).unwrap(),
+            [Action::Shell("notify-send single".to_string())]
+        );
+        assert_eq!(
+            map.lookup(parse_key_binding("double-mouse-left").unwrap()),
+            Some(KeyBinding::from(0x110))
+        );
+        assert_eq!(map.scroll_thresholds.get(&SCROLL_UP), Some(&30.));
+        // a single command is written back as a string
+        assert_eq!(
+            serde_jsonrc::to_value(&map).unwrap()["mouse-right"],
+            json!("notify-send single")
+        );
+
+        assert!(
+            serde_jsonrc::from_value::<KeyEventMap>(json!({"mouse-left": [["toggle-pin"]]}))
+                .is_err()
+        );
+    }
 }
 
 pub fn option_color_translate<'de, D>(d: D) -> Result<Option<Color>, D::Error>
@@ -491,6 +1009,27 @@ where
     }
 }
 
+pub fn serialize_color<S: Serializer>(c: &Color, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_str(&color_to_hex(*c))
+}
+
+pub fn serialize_optional_color<S: Serializer>(c: &Option<Color>, s: S) -> Result<S::Ok, S::Error> {
+    match c {
+        Some(c) => serialize_color(c, s),
+        None => s.serialize_none(),
+    }
+}
+
+pub fn serialize_optional_template<S: Serializer>(
+    t: &Option<Template>,
+    s: S,
+) -> Result<S::Ok, S::Error> {
+    match t {
+        Some(t) => s.serialize_str(t.source()),
+        None => s.serialize_none(),
+    }
+}
+
 pub fn from_value<T>(v: Value) -> Result<T, String>
 where
     T: serde::de::DeserializeOwned,
@@ -538,6 +1077,17 @@ pub fn parse_family_owned(s: &str) -> Result<FamilyOwned, Infallible> {
     })
 }
 
+pub fn serialize_family_owned<S: Serializer>(f: &FamilyOwned, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_str(match f {
+        FamilyOwned::Serif => "serif",
+        FamilyOwned::SansSerif => "sans-serif",
+        FamilyOwned::Cursive => "cursive",
+        FamilyOwned::Fantasy => "fantasy",
+        FamilyOwned::Monospace => "monospace",
+        FamilyOwned::Name(name) => name,
+    })
+}
+
 pub fn deserialize_family_owned<'de, D>(d: D) -> Result<FamilyOwned, D::Error>
 where
     D: Deserializer<'de>,
@@ -575,7 +1125,7 @@ pub fn schema_family_owned(_: &mut schemars::SchemaGenerator) -> schemars::Schem
     })
 }
 
-#[derive(Debug, Clone, Decode, PartialEq, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Decode, PartialEq, Deserialize, Serialize, JsonSchema)]
 pub struct NumMargins {
     #[knus(child, default, unwrap(argument))]
     #[serde(default)]
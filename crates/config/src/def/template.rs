@@ -0,0 +1,678 @@
+use std::{any::Any, collections::HashMap, mem, ops::Deref};
+
+use knus::{
+    ast::{Literal, SpannedNode},
+    errors::DecodeError,
+    span::Spanned,
+    traits::ErrorSpan,
+};
+use serde_jsonrc::Value;
+
+use super::{common::CommonConfig, util::argv_str};
+
+/// `template` and `defaults` of every file of a load.
+#[derive(Default)]
+pub struct Templates {
+    kdl: HashMap<String, Box<dyn Any>>,
+    json: HashMap<String, Value>,
+    // by widget type, "" for every widget
+    defaults: HashMap<String, Box<dyn Any>>,
+    // children block of a `defaults`, given to widgets without a block of their own
+    defaults_block: Option<Box<dyn Any>>,
+    json_defaults: HashMap<String, Value>,
+}
+
+const WIDGET_TYPES: &[&str] = &["btn", "slider", "workspace", "wrap-box"];
+
+// what `defaults` for every widget may set, and what no `defaults` may
+fn check_default_field(widget_type: &str, field: &str) -> Result<(), String> {
+    if matches!(field, "namespace" | "extends") {
+        return Err(format!("`{field}` can't have a default"));
+    }
+    if widget_type.is_empty() && !CommonConfig::KDL_CHILDREN.contains(&field) {
+        return Err(format!(
+            "`{field}` is not shared by every widget, put it in `defaults \"<type>\"`"
+        ));
+    }
+    Ok(())
+}
+
+/// `template "name" { ... }`
+pub fn define_template<S: ErrorSpan + 'static>(
+    node: &SpannedNode<S>,
+    ctx: &mut knus::decode::Context<S>,
+    templates: &mut Templates,
+) -> Result<(), DecodeError<S>> {
+    let name = argv_str(node, ctx)?;
+    templates.kdl.insert(name, Box::new(node.clone()));
+    Ok(())
+}
+
+fn get_template<S: ErrorSpan + 'static>(
+    templates: &Templates,
+    name: &str,
+) -> Option<SpannedNode<S>> {
+    templates
+        .kdl
+        .get(name)
+        .and_then(|n| n.downcast_ref::<SpannedNode<S>>())
+        .cloned()
+}
+
+fn same_arguments<S: ErrorSpan>(a: &SpannedNode<S>, b: &SpannedNode<S>) -> bool {
+    a.arguments.len() == b.arguments.len()
+        && a.arguments
+            .iter()
+            .zip(b.arguments.iter())
+            .all(|(a, b)| a.literal.deref() == b.literal.deref())
+}
+
+// nodes that can be given more than once, `over` adds to the ones of `base`
+const REPEATED: &[&str] = &["item", "env"];
+
+// children of `over` replace the ones with the same name in `base`,
+// blocks with the same arguments, like `preset "custom" {}`, are merged recursively.
+// bindings of `event-map` are replaced as a whole.
+fn merge_children<S: ErrorSpan>(
+    parent: &str,
+    mut base: Vec<SpannedNode<S>>,
+    over: Vec<SpannedNode<S>>,
+) -> Vec<SpannedNode<S>> {
+    for mut o in over {
+        let name = o.node_name.to_string();
+        let found = if REPEATED.contains(&name.as_str()) {
+            None
+        } else {
+            base.iter_mut().find(|b| b.node_name.as_ref() == name)
+        };
+        let Some(b) = found else {
+            base.push(o);
+            continue;
+        };
+        let same = parent != "event-map" && same_arguments(b, &o);
+        if let (true, Some(bc), Some(oc)) = (same, b.children.as_mut(), o.children.as_mut()) {
+            let merged = merge_children(&name, mem::take(&mut **bc), mem::take(&mut **oc));
+            **bc = merged;
+        } else {
+            *b = o;
+        }
+    }
+    base
+}
+
+// take `extends` out of the children and return the merged children of the templates
+fn resolve_extends<S: ErrorSpan + 'static>(
+    children: &mut Vec<SpannedNode<S>>,
+    stack: &mut Vec<String>,
+    templates: &Templates,
+) -> Result<Vec<SpannedNode<S>>, DecodeError<S>> {
+    let (extends, rest) = mem::take(children)
+        .into_iter()
+        .partition::<Vec<_>, _>(|c| c.node_name.as_ref() == "extends");
+    *children = rest;
+
+    let mut base = vec![];
+    for node in extends.iter() {
+        for arg in node.arguments.iter() {
+            let Literal::String(name) = arg.literal.deref() else {
+                return Err(DecodeError::unsupported(
+                    &arg.literal,
+                    "template name must be a string",
+                ));
+            };
+            if stack.iter().any(|n| n.as_str() == name.as_ref()) {
+                return Err(DecodeError::conversion(
+                    &arg.literal,
+                    format!("template `{name}` extends itself"),
+                ));
+            }
+            let Some(template) = get_template::<S>(templates, name) else {
+                return Err(DecodeError::conversion(
+                    &arg.literal,
+                    format!("unknown template `{name}`"),
+                ));
+            };
+
+            let mut template_children = template
+                .children
+                .as_ref()
+                .map(|c| c.deref().clone())
+                .unwrap_or_default();
+            stack.push(name.to_string());
+            let template_base = resolve_extends(&mut template_children, stack, templates)?;
+            stack.pop();
+
+            base = merge_children(
+                "",
+                base,
+                merge_children("", template_base, template_children),
+            );
+        }
+    }
+    Ok(base)
+}
+
+/// merge the templates named by `extends` children into the node, fields of the node win.
+pub fn apply_templates<S: ErrorSpan + 'static>(
+    node: &mut SpannedNode<S>,
+    templates: &Templates,
+) -> Result<(), DecodeError<S>> {
+    let Some(children) = node.children.as_mut() else {
+        return Ok(());
+    };
+    let base = resolve_extends(&mut **children, &mut vec![], templates)?;
+    if !base.is_empty() {
+        let own = mem::take(&mut **children);
+        **children = merge_children("", base, own);
+    }
+    Ok(())
+}
+
+/// `defaults { ... }` for every widget, or `defaults "slider" { ... }` for one type.
+pub fn define_defaults<S: ErrorSpan + 'static>(
+    node: &SpannedNode<S>,
+    ctx: &mut knus::decode::Context<S>,
+    templates: &mut Templates,
+) -> Result<(), DecodeError<S>> {
+    let widget_type = match node.arguments.first() {
+        Some(_) => argv_str(node, ctx)?,
+        None => String::new(),
+    };
+    if !widget_type.is_empty() && !WIDGET_TYPES.contains(&widget_type.as_str()) {
+        return Err(DecodeError::conversion(
+            &node.arguments[0].literal,
+            format!(
+                "unknown widget type `{widget_type}`, expected one of: {}",
+                WIDGET_TYPES.join(", ")
+            ),
+        ));
+    }
+    let children = node
+        .children
+        .as_ref()
+        .map(|c| c.deref().clone())
+        .unwrap_or_default();
+    for c in children.iter() {
+        check_default_field(&widget_type, c.node_name.as_ref())
+            .map_err(|e| DecodeError::unexpected(&c.node_name, "node", e))?;
+    }
+
+    // later blocks win
+    if let Some(block) = node.children.as_ref() {
+        templates.defaults_block = Some(Box::new(block.clone()));
+    }
+    let base = get_defaults::<S>(templates, &widget_type);
+    let merged = merge_children("", base, children);
+    templates.defaults.insert(widget_type, Box::new(merged));
+    Ok(())
+}
+
+fn get_defaults<S: ErrorSpan + 'static>(
+    templates: &Templates,
+    widget_type: &str,
+) -> Vec<SpannedNode<S>> {
+    templates
+        .defaults
+        .get(widget_type)
+        .and_then(|n| n.downcast_ref::<Vec<SpannedNode<S>>>())
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// merge `defaults` into a widget node, after `apply_templates`.
+pub fn apply_defaults<S: ErrorSpan + 'static>(node: &mut SpannedNode<S>, templates: &Templates) {
+    let base = merge_children(
+        "",
+        get_defaults(templates, ""),
+        get_defaults(templates, node.node_name.as_ref()),
+    );
+    if base.is_empty() {
+        return;
+    }
+    let children = match node.children.as_mut() {
+        Some(children) => children,
+        // everything comes from `defaults`
+        None => {
+            let Some(block) = templates
+                .defaults_block
+                .as_ref()
+                .and_then(|b| b.downcast_ref::<Spanned<Vec<SpannedNode<S>>, S>>())
+            else {
+                return;
+            };
+            let mut block = block.clone();
+            block.clear();
+            node.children.insert(block)
+        }
+    };
+    let own = mem::take(&mut **children);
+    **children = merge_children("", base, own);
+}
+
+/// json version of `define_template`, `"templates": { "name": { ... } }`.
+pub fn define_json_templates(value: Value, templates: &mut Templates) -> Result<(), String> {
+    let Value::Object(map) = value else {
+        return Err("templates must be an object".to_string());
+    };
+    templates.json.extend(map);
+    Ok(())
+}
+
+// objects are merged recursively, `items` of `over` are added to the ones of `base`,
+// anything else in `over` replaces `base`
+fn merge_json(base: &mut Value, over: Value) {
+    match (base, over) {
+        (Value::Object(b), Value::Object(o)) => {
+            for (k, v) in o {
+                match (b.get_mut(&k), v) {
+                    (Some(Value::Array(bv)), Value::Array(v)) if k == "items" => bv.extend(v),
+                    (Some(bv), v) => merge_json(bv, v),
+                    (None, v) => {
+                        b.insert(k, v);
+                    }
+                }
+            }
+        }
+        (b, o) => *b = o,
+    }
+}
+
+fn resolve_json_extends(
+    value: &mut Value,
+    stack: &mut Vec<String>,
+    templates: &Templates,
+) -> Result<Value, String> {
+    let extends = match value.as_object_mut().and_then(|o| o.remove("extends")) {
+        None => return Ok(Value::Null),
+        Some(Value::String(s)) => vec![s],
+        Some(Value::Array(list)) => list
+            .into_iter()
+            .map(|v| match v {
+                Value::String(s) => Ok(s),
+                _ => Err("template name must be a string".to_string()),
+            })
+            .collect::<Result<_, _>>()?,
+        Some(_) => return Err("extends must be a string or a list of strings".to_string()),
+    };
+
+    let mut base = Value::Null;
+    for name in extends {
+        if stack.contains(&name) {
+            return Err(format!("template `{name}` extends itself"));
+        }
+        let Some(mut template) = templates.json.get(&name).cloned() else {
+            return Err(format!("unknown template `{name}`"));
+        };
+
+        stack.push(name);
+        let mut template_base = resolve_json_extends(&mut template, stack, templates)?;
+        stack.pop();
+
+        merge_json(&mut template_base, template);
+        merge_json(&mut base, template_base);
+    }
+    Ok(base)
+}
+
+/// json version of `define_defaults`, `"defaults": { "layer": "overlay", "slider": { ... } }`.
+pub fn define_json_defaults(value: Value, templates: &mut Templates) -> Result<(), String> {
+    let Value::Object(map) = value else {
+        return Err("defaults must be an object".to_string());
+    };
+    let mut common = Value::Object(Default::default());
+    for (k, v) in map {
+        if !WIDGET_TYPES.contains(&k.as_str()) {
+            check_default_field("", &k)?;
+            merge_json(&mut common, Value::Object([(k, v)].into_iter().collect()));
+            continue;
+        }
+        let Value::Object(fields) = &v else {
+            return Err(format!("defaults of `{k}` must be an object"));
+        };
+        for f in fields.keys() {
+            check_default_field(&k, f)?;
+        }
+        merge_json(templates.json_defaults.entry(k).or_insert(Value::Null), v);
+    }
+    merge_json(
+        templates
+            .json_defaults
+            .entry(String::new())
+            .or_insert(Value::Null),
+        common,
+    );
+    Ok(())
+}
+
+/// json version of `apply_defaults`, returns whether anything was merged.
+pub fn apply_json_defaults(widget: &mut Value, templates: &Templates) -> bool {
+    let widget_type = widget
+        .get("type")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let mut base = Value::Null;
+    for t in ["", widget_type.as_str()] {
+        if let Some(v) = templates.json_defaults.get(t) {
+            merge_json(&mut base, v.clone());
+        }
+    }
+    if !matches!(&base, Value::Object(o) if !o.is_empty()) {
+        return false;
+    }
+    merge_json(&mut base, mem::take(widget));
+    *widget = base;
+    true
+}
+
+/// json version of `apply_templates`, returns whether the widget extends anything.
+pub fn apply_json_templates(widget: &mut Value, templates: &Templates) -> Result<bool, String> {
+    let mut base = resolve_json_extends(widget, &mut vec![], templates)?;
+    if base.is_null() {
+        return Ok(false);
+    }
+    merge_json(&mut base, mem::take(widget));
+    *widget = base;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::def::{parse_jsonc, Root, WidgetConf};
+    use util::color::parse_color;
+
+    #[test]
+    fn test_decode_template() {
+        let kdl = r##"
+template "base" {
+    edge "bottom"
+    thickness 20
+    length "40%"
+    color "#ff0000"
+    border-width 3
+}
+template "wide" {
+    extends "base"
+    length "80%"
+}
+btn {
+    extends "wide"
+    border-width 5
+}
+"##;
+        let root: Root = knus::parse("test", kdl).unwrap();
+        let WidgetConf::Btn(btn) = &root.widgets[0] else {
+            panic!("Expected Btn");
+        };
+        assert_eq!(btn.widget.color, parse_color("#ff0000").unwrap());
+        assert_eq!(btn.widget.border_width, 5);
+        assert_eq!(
+            btn.widget.size.length,
+            crate::def::shared::NumOrRelative::Relative(0.8)
+        );
+    }
+
+    #[test]
+    fn test_template_items() {
+        let kdl = r##"
+template "box" {
+    edge "top"
+    thickness 20
+    length "40%"
+    item "text" {
+        index 0 0
+        preset "time" {
+        }
+    }
+    when {
+        env "A"
+    }
+}
+template "keys" {
+    event-map {
+        mouse-left {
+            shell "a"
+            shell "b"
+        }
+    }
+}
+btn {
+    extends "keys"
+    edge "top"
+    thickness 20
+    length "40%"
+    event-map {
+        mouse-left {
+            shell "c"
+        }
+    }
+}
+wrap-box {
+    extends "box"
+    item "ring" {
+        index 0 1
+        preset "ram" {
+        }
+    }
+    item "text" {
+        index 1 0
+        preset "time" {
+        }
+    }
+    when {
+        env "B"
+    }
+}
+"##;
+        let root: Root = knus::parse("test", kdl).unwrap();
+        let WidgetConf::Btn(btn) = &root.widgets[0] else {
+            panic!("Expected Btn");
+        };
+        assert_eq!(
+            btn.widget
+                .event_map
+                .get_actions(&crate::def::shared::KeyBinding::from(0x110))
+                .unwrap(),
+            [crate::def::action::Action::Shell("c".to_string())]
+        );
+        let WidgetConf::WrapBox(w) = &root.widgets[1] else {
+            panic!("Expected WrapBox");
+        };
+        let indices: Vec<_> = w.widget.items.iter().map(|i| i.index).collect();
+        assert_eq!(indices, [[0, 0], [0, 1], [1, 0]]);
+        assert_eq!(w.common.when.env.len(), 2);
+
+        let json = r##"{
+    "templates": {
+        "box": {
+            "edge": "top", "thickness": 20, "length": "40%",
+            "items": [{ "type": "text", "index": [0, 0], "preset": "time" }]
+        }
+    },
+    "widgets": [{
+        "type": "wrap-box",
+        "extends": "box",
+        "items": [
+            { "type": "ring", "index": [0, 1], "preset": "ram" },
+            { "type": "text", "index": [1, 0], "preset": "time" }
+        ]
+    }]
+}"##;
+        let root = parse_jsonc(json).unwrap();
+        let WidgetConf::WrapBox(w) = &root.widgets[0] else {
+            panic!("Expected WrapBox");
+        };
+        assert_eq!(w.widget.items.len(), 3);
+    }
+
+    #[test]
+    fn test_template_cycle() {
+        let kdl = r##"
+template "a" {
+    extends "b"
+}
+template "b" {
+    extends "a"
+}
+btn {
+    extends "a"
+}
+"##;
+        assert!(knus::parse::<Root>("test", kdl).is_err());
+    }
+
+    #[test]
+    fn test_decode_defaults() {
+        let kdl = r##"
+defaults {
+    transition-duration 100
+    layer "overlay"
+}
+defaults "slider" {
+    transition-duration 200
+    border-width 7
+}
+template "base" {
+    edge "bottom"
+    thickness 20
+    length "40%"
+}
+btn {
+    extends "base"
+}
+slider {
+    extends "base"
+    pin-on-startup
+}
+slider {
+    extends "base"
+    transition-duration 50
+}
+"##;
+        let root: Root = knus::parse("test", kdl).unwrap();
+        let durations: Vec<_> = root
+            .widgets
+            .iter()
+            .map(|w| w.common().transition_duration)
+            .collect();
+        assert_eq!(durations, [100, 200, 50]);
+        assert!(root
+            .widgets
+            .iter()
+            .all(|w| w.common().layer == smithay_client_toolkit::shell::wlr_layer::Layer::Overlay));
+        let WidgetConf::Slider(slider) = &root.widgets[1] else {
+            panic!("Expected Slider");
+        };
+        assert_eq!(slider.widget.border_width, 7);
+        assert!(slider.common.pin_on_startup);
+
+        for invalid in [
+            r#"defaults { border-width 3; }"#,
+            r#"defaults { namespace "a"; }"#,
+            r#"defaults "button" { layer "overlay"; }"#,
+        ] {
+            assert!(knus::parse::<Root>("test", invalid).is_err());
+        }
+    }
+
+    #[test]
+    fn test_defaults_items_and_flags() {
+        let kdl = r##"
+defaults {
+    pin-on-startup
+}
+defaults "wrap-box" {
+    item "text" {
+        index 0 0
+        preset "time" {
+        }
+    }
+    item "ring" {
+        index 0 1
+        preset "ram" {
+        }
+    }
+}
+wrap-box {
+    edge "top"
+    item "text" {
+        index 1 0
+        preset "time" {
+        }
+    }
+}
+btn {
+    edge "top"
+    thickness 20
+    length "40%"
+    pin-on-startup false
+}
+"##;
+        let root: Root = knus::parse("test", kdl).unwrap();
+        let WidgetConf::WrapBox(w) = &root.widgets[0] else {
+            panic!("Expected WrapBox");
+        };
+        let indices: Vec<_> = w.widget.items.iter().map(|i| i.index).collect();
+        assert_eq!(indices, [[0, 0], [0, 1], [1, 0]]);
+        assert!(w.common.pin_on_startup);
+        assert!(!root.widgets[1].common().pin_on_startup);
+    }
+
+    #[test]
+    fn test_defaults_bare_widget() {
+        let kdl = r##"
+defaults {
+    edge "left"
+}
+defaults "btn" {
+    thickness 20
+    length "40%"
+}
+btn
+"##;
+        let root: Root = knus::parse("test", kdl).unwrap();
+        assert_eq!(
+            root.widgets[0].common().edge,
+            smithay_client_toolkit::shell::wlr_layer::Anchor::LEFT
+        );
+    }
+
+    #[test]
+    fn test_decode_json_defaults() {
+        let json = r##"{
+    "defaults": {
+        "transition-duration": 100,
+        "slider": { "transition-duration": 200 }
+    },
+    "widgets": [
+        { "type": "btn", "edge": "bottom", "thickness": 20, "length": "40%" },
+        { "type": "slider", "edge": "bottom", "thickness": 20, "length": "40%" },
+        { "type": "btn", "edge": "bottom", "thickness": 20, "length": "40%", "transition-duration": 50 }
+    ]
+}"##;
+        let root = parse_jsonc(json).unwrap();
+        let durations: Vec<_> = root
+            .widgets
+            .iter()
+            .map(|w| w.common().transition_duration)
+            .collect();
+        assert_eq!(durations, [100, 200, 50]);
+
+        assert!(parse_jsonc(r#"{ "defaults": { "thickness": 20 }, "widgets": [] }"#).is_err());
+    }
+
+    #[test]
+    fn test_decode_json_template() {
+        let json = r##"{
+    "templates": {
+        "base": { "edge": "bottom", "thickness": 20, "length": "40%", "border-width": 3 }
+    },
+    "widgets": [{ "type": "btn", "extends": "base", "border-width": 5 }]
+}"##;
+        let root = parse_jsonc(json).unwrap();
+        let WidgetConf::Btn(btn) = &root.widgets[0] else {
+            panic!("Expected Btn");
+        };
+        assert_eq!(btn.widget.border_width, 5);
+    }
+}
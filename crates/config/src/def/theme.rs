@@ -0,0 +1,315 @@
+use std::{collections::HashMap, ops::Deref};
+
+use knus::{ast::Literal, errors::DecodeError, traits::ErrorSpan};
+use regex_lite::Regex;
+use serde_jsonrc::Value;
+use util::color::{color_lighten, color_to_hex, color_with_alpha, parse_color};
+
+use super::util::argv;
+
+#[derive(Debug, Clone)]
+enum ThemeValue {
+    Str(String),
+    Int(i64),
+    Float(f64),
+}
+
+#[derive(Debug, Clone)]
+struct ThemeVar {
+    value: ThemeValue,
+    // numbers written in kdl, reused as is so integer fields accept them
+    literal: Option<Literal>,
+}
+impl ThemeVar {
+    fn to_literal(&self) -> Literal {
+        if let Some(literal) = self.literal.as_ref() {
+            return literal.clone();
+        }
+        match &self.value {
+            ThemeValue::Str(s) => Literal::String(s.as_str().into()),
+            ThemeValue::Int(i) => Literal::String(i.to_string().into()),
+            ThemeValue::Float(f) => Literal::String(f.to_string().into()),
+        }
+    }
+    fn to_json(&self) -> Value {
+        match &self.value {
+            ThemeValue::Str(s) => Value::from(s.as_str()),
+            ThemeValue::Int(i) => Value::from(*i),
+            ThemeValue::Float(f) => Value::from(*f),
+        }
+    }
+}
+
+/// variables of every `theme` of a load, the files of it share them.
+#[derive(Debug, Default)]
+pub struct Theme(HashMap<String, ThemeVar>);
+impl Theme {
+    fn define(&mut self, name: &str, var: ThemeVar) {
+        self.0.insert(name.to_string(), var);
+    }
+
+    fn lookup(&self, name: &str) -> Option<ThemeVar> {
+        self.0.get(name).cloned()
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref VAR_REGEX: Regex = Regex::new(r"^\$([\w-]+)$").unwrap();
+    static ref ALPHA_REGEX: Regex =
+        Regex::new(r"^\$([\w-]+)\s*/\s*(\d+(?:\.\d+)?)%$").unwrap();
+    static ref LIGHTEN_REGEX: Regex =
+        Regex::new(r"^(lighten|darken)\(\s*(.+?)\s*,\s*(\d+(?:\.\d+)?)%\s*\)$").unwrap();
+}
+
+fn color_of(theme: &Theme, operand: &str) -> Result<cosmic_text::Color, String> {
+    let s = match operand.strip_prefix('$') {
+        Some(name) => match theme.lookup(name).map(|v| v.value) {
+            Some(ThemeValue::Str(s)) => s,
+            Some(_) => return Err(format!("theme variable `{name}` is not a color")),
+            None => return Err(format!("unknown theme variable `{name}`")),
+        },
+        None => operand.to_string(),
+    };
+    parse_color(&s).map_err(|e| format!("invalid color `{s}`: {e}"))
+}
+
+/// `$name`, `$name / 50%`, `lighten($name, 10%)` or `darken($name, 10%)`.
+/// `None` if the string does not reference the theme, `$name` of an unknown
+/// variable is left as is since it can be a shell variable in commands.
+fn resolve(theme: &Theme, s: &str) -> Option<Result<ThemeVar, String>> {
+    let s = s.trim();
+
+    if let Some(caps) = VAR_REGEX.captures(s) {
+        return theme.lookup(&caps[1]).map(Ok);
+    }
+
+    let color = if let Some(caps) = ALPHA_REGEX.captures(s) {
+        let alpha = caps[2].parse::<f64>().unwrap() / 100.;
+        color_of(theme, &format!("${}", &caps[1])).map(|c| color_with_alpha(c, alpha))
+    } else if let Some(caps) = LIGHTEN_REGEX.captures(s) {
+        let mut amount = caps[3].parse::<f64>().unwrap() / 100.;
+        if &caps[1] == "darken" {
+            amount = -amount;
+        }
+        color_of(theme, &caps[2]).map(|c| color_lighten(c, amount))
+    } else {
+        return None;
+    };
+
+    Some(color.map(|c| ThemeVar {
+        value: ThemeValue::Str(color_to_hex(c)),
+        literal: None,
+    }))
+}
+
+/// `theme { accent "#7B98FF"; gap 4; }`, values can use variables defined before.
+pub fn decode_theme<S: ErrorSpan>(
+    node: &knus::ast::SpannedNode<S>,
+    theme: &mut Theme,
+) -> Result<(), DecodeError<S>> {
+    for child in node.children() {
+        let arg = argv(child)?;
+        let var = match arg.literal.deref() {
+            Literal::String(s) => match resolve(theme, s) {
+                Some(res) => res.map_err(|e| DecodeError::conversion(&arg.literal, e))?,
+                None => ThemeVar {
+                    value: ThemeValue::Str(s.to_string()),
+                    literal: None,
+                },
+            },
+            Literal::Int(i) => ThemeVar {
+                value: ThemeValue::Int(
+                    TryInto::<i64>::try_into(i)
+                        .map_err(|e| DecodeError::conversion(&arg.literal, e))?,
+                ),
+                literal: Some(arg.literal.deref().clone()),
+            },
+            Literal::Decimal(d) => ThemeVar {
+                value: ThemeValue::Float(
+                    TryInto::<f64>::try_into(d)
+                        .map_err(|e| DecodeError::conversion(&arg.literal, e))?,
+                ),
+                literal: Some(arg.literal.deref().clone()),
+            },
+            _ => {
+                return Err(DecodeError::unsupported(
+                    &arg.literal,
+                    "theme value must be a string or a number",
+                ))
+            }
+        };
+        theme.define(&child.node_name, var);
+    }
+    Ok(())
+}
+
+fn substitute_value<S: ErrorSpan>(
+    value: &mut knus::ast::Value<S>,
+    theme: &Theme,
+) -> Result<(), DecodeError<S>> {
+    let Literal::String(s) = value.literal.deref() else {
+        return Ok(());
+    };
+    match resolve(theme, s) {
+        Some(Ok(var)) => {
+            *value.literal = var.to_literal();
+            Ok(())
+        }
+        Some(Err(e)) => Err(DecodeError::conversion(&value.literal, e)),
+        None => Ok(()),
+    }
+}
+
+/// replace theme references in the arguments and properties of the node and its children.
+pub fn substitute_node<S: ErrorSpan>(
+    node: &mut knus::ast::SpannedNode<S>,
+    theme: &Theme,
+) -> Result<(), DecodeError<S>> {
+    for v in node.arguments.iter_mut() {
+        substitute_value(v, theme)?;
+    }
+    for v in node.properties.values_mut() {
+        substitute_value(v, theme)?;
+    }
+    if let Some(children) = node.children.as_mut() {
+        for c in children.iter_mut() {
+            substitute_node(c, theme)?;
+        }
+    }
+    Ok(())
+}
+
+/// json version of `decode_theme`, `{ "accent": "#7B98FF", "gap": 4 }`.
+pub fn decode_json_theme(value: &Value, theme: &mut Theme) -> Result<(), String> {
+    let Value::Object(map) = value else {
+        return Err("theme must be an object".to_string());
+    };
+    for (name, v) in map {
+        let value = match v {
+            Value::String(s) => match resolve(theme, s) {
+                Some(res) => res?.value,
+                None => ThemeValue::Str(s.clone()),
+            },
+            Value::Number(n) => match n.as_i64() {
+                Some(i) => ThemeValue::Int(i),
+                None => ThemeValue::Float(n.as_f64().unwrap_or_default()),
+            },
+            _ => {
+                return Err(format!(
+                    "theme value of `{name}` must be a string or a number"
+                ))
+            }
+        };
+        theme.define(
+            name,
+            ThemeVar {
+                value,
+                literal: None,
+            },
+        );
+    }
+    Ok(())
+}
+
+/// replace theme references in every string, returns whether anything changed.
+pub fn substitute_json(value: &mut Value, theme: &Theme) -> Result<bool, String> {
+    match value {
+        Value::String(s) => match resolve(theme, s) {
+            Some(var) => {
+                *value = var?.to_json();
+                Ok(true)
+            }
+            None => Ok(false),
+        },
+        Value::Array(list) => {
+            list.iter_mut().try_fold(
+                false,
+                |changed, v| Ok(substitute_json(v, theme)? || changed),
+            )
+        }
+        Value::Object(map) => map.iter_mut().try_fold(false, |changed, (_, v)| {
+            Ok(substitute_json(v, theme)? || changed)
+        }),
+        _ => Ok(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::def::{action::Action, shared::KeyBinding, Root, WidgetConf};
+
+    #[test]
+    fn test_decode_theme() {
+        let kdl = r##"
+theme {
+    accent "#ff0000"
+    faded "$accent / 50%"
+    size 20
+    width 3
+}
+btn {
+    edge "bottom"
+    thickness "$size"
+    length "40%"
+    border-width "$width"
+    color "lighten($accent, 10%)"
+    border-color "$faded"
+    event-map {
+        mouse-left "echo $HOME"
+        mouse-right "$EDITOR"
+    }
+}
+"##;
+        let root: Root = knus::parse("test", kdl).unwrap();
+        let WidgetConf::Btn(btn) = &root.widgets[0] else {
+            panic!("Expected Btn");
+        };
+        assert_eq!(btn.widget.border_width, 3);
+        assert_eq!(btn.widget.color, parse_color("#ff3333").unwrap());
+        assert_eq!(btn.widget.border_color, parse_color("#ff000080").unwrap());
+        let map = &btn.widget.event_map;
+        assert_eq!(
+            map.get_actions(&KeyBinding::from(0x110)).unwrap(),
+            [Action::Shell("echo $HOME".to_string())]
+        );
+        assert_eq!(
+            map.get_actions(&KeyBinding::from(0x111)).unwrap(),
+            [Action::Shell("$EDITOR".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_decode_json_theme() {
+        let json = r##"{
+    "theme": { "accent": "#ff0000", "size": 20, "width": 3 },
+    "widgets": [{
+        "type": "btn",
+        "edge": "bottom",
+        "thickness": "$size",
+        "length": "40%",
+        "border-width": "$width",
+        "color": "darken($accent, 10%)"
+    }]
+}"##;
+        let root = crate::def::parse_jsonc(json).unwrap();
+        let WidgetConf::Btn(btn) = &root.widgets[0] else {
+            panic!("Expected Btn");
+        };
+        assert_eq!(btn.widget.border_width, 3);
+        assert_eq!(btn.widget.color, parse_color("#cc0000").unwrap());
+    }
+
+    #[test]
+    fn test_unknown_theme_variable() {
+        let kdl = r##"
+btn {
+    edge "bottom"
+    thickness 20
+    length "40%"
+    color "lighten($accent, 10%)"
+}
+"##;
+        assert!(knus::parse::<Root>("test", kdl).is_err());
+    }
+}
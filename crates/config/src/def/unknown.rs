@@ -0,0 +1,191 @@
+use knus::{ast::SpannedNode, errors::DecodeError, traits::ErrorSpan};
+
+use super::LoadContext;
+
+/// report a node no decoder knows about, an error in strict mode.
+pub fn unknown_node<S: ErrorSpan>(
+    node: &SpannedNode<S>,
+    known: &[&str],
+    ctx: &mut knus::decode::Context<S>,
+) {
+    let name = node.node_name.as_ref();
+    let mut msg = format!("unknown node `{name}`");
+    if let Some(s) = did_you_mean(name, known.iter().copied()) {
+        msg.push_str(&format!(", did you mean `{s}`?"));
+    }
+
+    let load = LoadContext::of(ctx);
+    if load.borrow().strict {
+        ctx.emit_error(DecodeError::unexpected(&node.node_name, "node", msg));
+    } else {
+        let span: miette::SourceSpan = node.node_name.span().clone().into();
+        load.borrow_mut().warnings.push((span.offset(), msg));
+    }
+}
+
+/// for nodes decoded by more than one decoder, like the common config and the widget config,
+/// none of them can tell a child is unknown alone.
+pub fn check_children<S: ErrorSpan>(
+    node: &SpannedNode<S>,
+    known: &[&[&str]],
+    ctx: &mut knus::decode::Context<S>,
+) {
+    for child in node.children() {
+        let name = child.node_name.as_ref();
+        if !known.iter().any(|k| k.contains(&name)) {
+            unknown_node(child, &known.concat(), ctx);
+        }
+    }
+}
+
+/// line and column in characters of a byte offset, both starting at 1.
+pub fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let mut offset = offset.min(source.len());
+    while !source.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let before = &source[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before[before.rfind('\n').map_or(0, |i| i + 1)..]
+        .chars()
+        .count()
+        + 1;
+    (line, column)
+}
+
+fn did_you_mean<'a>(name: &str, known: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let max_distance = (name.len() / 3).max(1);
+    known
+        .map(|k| (levenshtein(name, k), k))
+        .filter(|(d, _)| *d <= max_distance)
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, k)| k)
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cur = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                prev
+            } else {
+                prev.min(cur).min(row[j]) + 1
+            };
+            prev = cur;
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{mem, rc::Rc};
+
+    use super::*;
+    use crate::def::{parse_kdl, Root};
+
+    const KDL: &str = r#"
+btn {
+    edge "bottom"
+    thickness 20
+    length "40%"
+    bordr-width 3
+}
+sldier {
+}
+"#;
+
+    // warnings of a document as `test:line:column: message`
+    fn warnings(kdl: &str) -> Vec<String> {
+        let load = Rc::default();
+        parse_kdl("test", kdl, &load).unwrap();
+        let warnings = mem::take(&mut load.borrow_mut().warnings);
+        warnings
+            .into_iter()
+            .map(|(offset, msg)| {
+                let (line, column) = line_column(kdl, offset);
+                format!("test:{line}:{column}: {msg}")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_did_you_mean() {
+        let known = ["border-width", "border-color", "color"];
+        assert_eq!(
+            did_you_mean("bordr-width", known.into_iter()),
+            Some("border-width")
+        );
+        assert_eq!(did_you_mean("colour", known.into_iter()), Some("color"));
+        assert_eq!(did_you_mean("something", known.into_iter()), None);
+    }
+
+    #[test]
+    fn test_unknown_node_warning() {
+        assert_eq!(
+            warnings(KDL),
+            [
+                "test:6:5: unknown node `bordr-width`, did you mean `border-width`?",
+                "test:8:1: unknown node `sldier`, did you mean `slider`?",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_line_column() {
+        let source = "a\n\"äö\" b";
+        assert_eq!(line_column(source, 0), (1, 1));
+        assert_eq!(line_column(source, 8), (2, 5));
+        // inside `ä`
+        assert_eq!(line_column(source, 4), (2, 2));
+        assert_eq!(line_column(source, 100), (2, 7));
+    }
+
+    #[test]
+    fn test_unknown_node_strict() {
+        assert!(knus::parse::<Root>("test", &format!("strict\n{KDL}")).is_err());
+    }
+
+    #[test]
+    fn test_unknown_preset_child() {
+        let kdl = r#"
+slider {
+    edge "left"
+    thickness 20
+    length "40%"
+    preset "speaker" {
+        devce "x"
+    }
+}
+workspace {
+    edge "top"
+    thickness 20
+    length "40%"
+    preset "hyprland" {
+        preserve-empty
+    }
+}
+wrap-box {
+    edge "bottom"
+    item "tray" {
+        header-draw-config {
+            font-size 20
+        }
+    }
+}
+"#;
+        assert_eq!(
+            warnings(kdl),
+            [
+                "test:7:9: unknown node `devce`, did you mean `device`?",
+                "test:15:9: unknown node `preserve-empty`",
+                "test:22:13: unknown node `font-size`",
+            ]
+        );
+        assert!(knus::parse::<Root>("test", &format!("strict\n{kdl}")).is_err());
+    }
+}
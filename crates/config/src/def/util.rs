@@ -60,6 +60,34 @@ pub fn argv_int<S: ErrorSpan>(
     i64::decode(arg, ctx)
 }
 
+/// length of `concat_names(lists)`.
+pub const fn names_len(lists: &[&[&str]]) -> usize {
+    let mut len = 0;
+    let mut i = 0;
+    while i < lists.len() {
+        len += lists[i].len();
+        i += 1;
+    }
+    len
+}
+
+/// `KDL_CHILDREN` of a config with the ones of its flattened fields, at compile time.
+pub const fn concat_names<const N: usize>(lists: &[&[&'static str]]) -> [&'static str; N] {
+    let mut names = [""; N];
+    let mut n = 0;
+    let mut i = 0;
+    while i < lists.len() {
+        let mut j = 0;
+        while j < lists[i].len() {
+            names[n] = lists[i][j];
+            n += 1;
+            j += 1;
+        }
+        i += 1;
+    }
+    names
+}
+
 pub trait ToKdlError<S: ErrorSpan> {
     type Ok;
     fn to_kdl_error(
@@ -0,0 +1,418 @@
+use std::{collections::BTreeMap, ops::Deref};
+
+use knus::{ast::Literal, errors::DecodeError, traits::ErrorSpan};
+use schemars::{json_schema, JsonSchema};
+use serde::{ser::SerializeMap, Deserialize, Serialize, Serializer};
+use serde_jsonrc::Value;
+
+use super::{common::TextMatch, unknown};
+
+pub const COMPOSITORS: &[&str] = &["hyprland", "niri"];
+
+const WHEN_NODES: &[&str] = &["compositor", "env", "hostname", "output-count"];
+
+/// what the widget is checked against when it's built.
+#[derive(Debug, Default)]
+pub struct Environment<'a> {
+    /// one of [`COMPOSITORS`], or none if it's neither
+    pub compositor: Option<&'a str>,
+    pub output_count: usize,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum CountOp {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+/// `">=2"`, `"<3"`, `"!=1"`, `"2"`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct OutputCount {
+    pub op: CountOp,
+    pub count: usize,
+}
+impl OutputCount {
+    fn parse(s: &str) -> Result<Self, String> {
+        let s = s.trim();
+        let (op, n) = [
+            (">=", CountOp::Ge),
+            ("<=", CountOp::Le),
+            ("!=", CountOp::Ne),
+            ("==", CountOp::Eq),
+            (">", CountOp::Gt),
+            ("<", CountOp::Lt),
+            ("=", CountOp::Eq),
+        ]
+        .into_iter()
+        .find_map(|(p, op)| s.strip_prefix(p).map(|n| (op, n)))
+        .unwrap_or((CountOp::Eq, s));
+        let count = n.trim().parse().map_err(|_| {
+            format!("Invalid output count `{s}`, expected a number after one of `>=`, `<=`, `>`, `<`, `=`, `!=`")
+        })?;
+        Ok(Self { op, count })
+    }
+
+    fn matches(&self, n: usize) -> bool {
+        match self.op {
+            CountOp::Eq => n == self.count,
+            CountOp::Ne => n != self.count,
+            CountOp::Gt => n > self.count,
+            CountOp::Ge => n >= self.count,
+            CountOp::Lt => n < self.count,
+            CountOp::Le => n <= self.count,
+        }
+    }
+}
+
+impl std::fmt::Display for OutputCount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let op = match self.op {
+            CountOp::Eq => "",
+            CountOp::Ne => "!=",
+            CountOp::Gt => ">",
+            CountOp::Ge => ">=",
+            CountOp::Lt => "<",
+            CountOp::Le => "<=",
+        };
+        write!(f, "{op}{}", self.count)
+    }
+}
+
+/// guards of a widget, it's only built when all of them hold.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct When {
+    /// any of them
+    pub compositor: Vec<String>,
+    /// variable name, and the value if it has to be one
+    pub env: BTreeMap<String, Option<TextMatch>>,
+    pub hostname: Option<TextMatch>,
+    pub output_count: Option<OutputCount>,
+}
+impl When {
+    pub fn is_met(&self, env: &Environment) -> bool {
+        if !self.compositor.is_empty()
+            && !env
+                .compositor
+                .is_some_and(|c| self.compositor.iter().any(|s| s == c))
+        {
+            return false;
+        }
+        if let Some(count) = &self.output_count {
+            if !count.matches(env.output_count) {
+                return false;
+            }
+        }
+        if let Some(hostname) = &self.hostname {
+            if !hostname.matches(&get_hostname()) {
+                return false;
+            }
+        }
+        self.env.iter().all(|(k, v)| match std::env::var(k) {
+            Ok(value) => v.as_ref().is_none_or(|v| v.matches(&value)),
+            Err(_) => false,
+        })
+    }
+
+    fn check_compositor(c: &str) -> Result<(), String> {
+        if COMPOSITORS.contains(&c) {
+            Ok(())
+        } else {
+            Err(format!(
+                "Unknown compositor `{c}`, supported: {}",
+                COMPOSITORS.join(", ")
+            ))
+        }
+    }
+}
+
+fn get_hostname() -> String {
+    std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .map(|s| s.trim().to_string())
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .unwrap_or_default()
+}
+
+fn string_args<S: ErrorSpan>(
+    node: &knus::ast::SpannedNode<S>,
+) -> Result<Vec<&str>, DecodeError<S>> {
+    node.arguments
+        .iter()
+        .map(|arg| match arg.literal.deref() {
+            Literal::String(s) => Ok(s.deref()),
+            _ => Err(DecodeError::unsupported(&arg.literal, "Expected a string")),
+        })
+        .collect()
+}
+
+impl<S: ErrorSpan> knus::Decode<S> for When {
+    fn decode_node(
+        node: &knus::ast::SpannedNode<S>,
+        ctx: &mut knus::decode::Context<S>,
+    ) -> Result<Self, DecodeError<S>> {
+        let mut when = Self::default();
+
+        for child in node.children() {
+            match child.node_name.as_ref() {
+                "compositor" => {
+                    for (c, arg) in string_args(child)?.into_iter().zip(&child.arguments) {
+                        Self::check_compositor(c)
+                            .map_err(|e| DecodeError::conversion(&arg.literal, e))?;
+                        when.compositor.push(c.to_string());
+                    }
+                }
+                "env" => {
+                    let args = string_args(child)?;
+                    let (name, value) = match args.as_slice() {
+                        [name] => (name, None),
+                        [name, value] => (
+                            name,
+                            Some(TextMatch::parse(value).map_err(|e| {
+                                DecodeError::conversion(&child.arguments[1].literal, e)
+                            })?),
+                        ),
+                        _ => {
+                            return Err(DecodeError::unexpected(
+                                &child.node_name,
+                                "env",
+                                "Expected a variable name and optionally its value",
+                            ))
+                        }
+                    };
+                    when.env.insert(name.to_string(), value);
+                }
+                "hostname" => {
+                    let [hostname] = string_args(child)?[..] else {
+                        return Err(DecodeError::unexpected(
+                            &child.node_name,
+                            "hostname",
+                            "Expected exactly one hostname",
+                        ));
+                    };
+                    when.hostname =
+                        Some(TextMatch::parse(hostname).map_err(|e| {
+                            DecodeError::conversion(&child.arguments[0].literal, e)
+                        })?);
+                }
+                "output-count" => {
+                    let Some(arg) = child.arguments.first() else {
+                        return Err(DecodeError::unexpected(
+                            &child.node_name,
+                            "output-count",
+                            "Expected a count like `2` or \">=2\"",
+                        ));
+                    };
+                    let count = match arg.literal.deref() {
+                        Literal::String(s) => OutputCount::parse(s),
+                        Literal::Int(i) => usize::try_from(i)
+                            .map(|count| OutputCount {
+                                op: CountOp::Eq,
+                                count,
+                            })
+                            .map_err(|_| "Invalid integer value encountered".to_string()),
+                        _ => Err("Expected a number or a string".to_string()),
+                    }
+                    .map_err(|e| DecodeError::conversion(&arg.literal, e))?;
+                    when.output_count = Some(count);
+                }
+                _ => unknown::unknown_node(child, WHEN_NODES, ctx),
+            }
+        }
+
+        Ok(when)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct RawWhen {
+    #[serde(default)]
+    compositor: Option<Value>,
+    #[serde(default)]
+    env: BTreeMap<String, Option<String>>,
+    #[serde(default)]
+    hostname: Option<String>,
+    #[serde(default)]
+    output_count: Option<Value>,
+}
+impl TryFrom<RawWhen> for When {
+    type Error = String;
+
+    fn try_from(raw: RawWhen) -> Result<Self, Self::Error> {
+        let compositor = match raw.compositor {
+            None => vec![],
+            Some(Value::String(s)) => vec![s],
+            Some(Value::Array(a)) => a
+                .into_iter()
+                .map(|v| match v {
+                    Value::String(s) => Ok(s),
+                    v => Err(format!("Invalid compositor: {v}")),
+                })
+                .collect::<Result<_, _>>()?,
+            Some(v) => return Err(format!("Invalid compositor: {v}")),
+        };
+        for c in &compositor {
+            Self::check_compositor(c)?;
+        }
+        let env = raw
+            .env
+            .into_iter()
+            .map(|(k, v)| Ok((k, v.as_deref().map(TextMatch::parse).transpose()?)))
+            .collect::<Result<_, String>>()?;
+        let hostname = raw.hostname.as_deref().map(TextMatch::parse).transpose()?;
+        let output_count = match raw.output_count {
+            None => None,
+            Some(Value::String(s)) => Some(OutputCount::parse(&s)?),
+            Some(Value::Number(n)) => Some(OutputCount {
+                op: CountOp::Eq,
+                count: n
+                    .as_u64()
+                    .ok_or_else(|| format!("Invalid output count: {n}"))?
+                    as usize,
+            }),
+            Some(v) => return Err(format!("Invalid output count: {v}")),
+        };
+
+        Ok(Self {
+            compositor,
+            env,
+            hostname,
+            output_count,
+        })
+    }
+}
+impl<'de> Deserialize<'de> for When {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        RawWhen::deserialize(deserializer)?
+            .try_into()
+            .map_err(serde::de::Error::custom)
+    }
+}
+impl Serialize for When {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let mut map = s.serialize_map(None)?;
+        if !self.compositor.is_empty() {
+            map.serialize_entry("compositor", &self.compositor)?;
+        }
+        if !self.env.is_empty() {
+            let env = self
+                .env
+                .iter()
+                .map(|(k, v)| (k, v.as_ref().map(|v| v.to_string())))
+                .collect::<BTreeMap<_, _>>();
+            map.serialize_entry("env", &env)?;
+        }
+        if let Some(hostname) = &self.hostname {
+            map.serialize_entry("hostname", &hostname.to_string())?;
+        }
+        if let Some(count) = &self.output_count {
+            map.serialize_entry("output-count", &count.to_string())?;
+        }
+        map.end()
+    }
+}
+impl JsonSchema for When {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "When".into()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        json_schema!({
+            "type": "object",
+            "additionalProperties": false,
+            "properties": {
+                "compositor": {
+                    "oneOf": [
+                        { "enum": COMPOSITORS },
+                        { "type": "array", "items": { "enum": COMPOSITORS } },
+                    ],
+                },
+                "env": {
+                    "type": "object",
+                    "additionalProperties": { "type": ["string", "null"] },
+                },
+                "hostname": { "type": "string" },
+                "output-count": {
+                    "type": ["string", "number"],
+                },
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_when(children: &str) -> When {
+        let kdl =
+            format!("btn {{ edge \"left\"; thickness 20; length 40; when {{ {children} }}; }}");
+        let root: crate::def::Root = knus::parse("test", &kdl).unwrap();
+        root.widgets[0].common().when.clone()
+    }
+
+    #[test]
+    fn test_when() {
+        let desk = Environment {
+            compositor: Some("niri"),
+            output_count: 2,
+        };
+        let laptop = Environment {
+            compositor: Some("hyprland"),
+            output_count: 1,
+        };
+
+        assert!(decode_when("").is_met(&laptop));
+
+        let w = decode_when(r#"compositor "niri"; output-count ">=2""#);
+        assert!(w.is_met(&desk) && !w.is_met(&laptop));
+
+        let w = decode_when("output-count 1");
+        assert!(!w.is_met(&desk) && w.is_met(&laptop));
+
+        let w = decode_when(r#"compositor "niri" "hyprland""#);
+        assert!(w.is_met(&desk) && w.is_met(&laptop));
+        assert!(!w.is_met(&Environment::default()));
+
+        std::env::set_var("WAY_EDGES_TEST_WHEN", "desk-1");
+        let w = decode_when(r#"env "WAY_EDGES_TEST_WHEN" "re:^desk""#);
+        assert!(w.is_met(&desk));
+        let w = decode_when(r#"env "WAY_EDGES_TEST_WHEN" "laptop""#);
+        assert!(!w.is_met(&desk));
+        let w = decode_when(r#"env "WAY_EDGES_TEST_WHEN_UNSET""#);
+        assert!(!w.is_met(&desk));
+    }
+
+    #[test]
+    fn test_when_invalid() {
+        for children in [r#"compositor "sway""#, r#"output-count ">=x""#, "env"] {
+            let kdl = format!(
+                "btn {{ edge \"left\"; thickness 20; length 40; when {{ {children}; }}; }}"
+            );
+            assert!(knus::parse::<crate::def::Root>("test", &kdl).is_err());
+        }
+    }
+
+    #[test]
+    fn test_json_when() {
+        let w: When = serde_jsonrc::from_str(
+            r#"{"compositor": "niri", "env": {"HOME": null}, "output-count": "<3"}"#,
+        )
+        .unwrap();
+        assert_eq!(w.compositor, ["niri"]);
+        assert_eq!(
+            w.output_count,
+            Some(OutputCount {
+                op: CountOp::Lt,
+                count: 3
+            })
+        );
+        assert!(serde_jsonrc::from_str::<When>(r#"{"compositor": ["sway"]}"#).is_err());
+    }
+}
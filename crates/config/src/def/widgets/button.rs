@@ -1,77 +1,29 @@
-use crate::def::shared::{color_translate, schema_color, CommonSize, KeyEventMap};
-use crate::def::util::{argv_str, argv_v, ToKdlError};
+use crate::def::shared::{CommonSize, KeyEventMap};
 use cosmic_text::Color;
-use schemars::JsonSchema;
-use serde::Deserialize;
 use util::color::{parse_color, COLOR_BLACK};
-use way_edges_derive::{const_property, GetSize};
+use way_edges_derive::{const_property, Config, GetSize};
 
 use schemars::Schema;
 use serde_json::Value;
 
-#[derive(Debug, GetSize, Clone, Deserialize, JsonSchema)]
+#[derive(Debug, GetSize, Clone, Config)]
 #[schemars(transform = BtnConfig_generate_defs)]
 #[schemars(deny_unknown_fields)]
 // FIXME: THIS DOES NOT WORK IDK WHY. so i have to add `transform` manually
 #[const_property("type", "btn")]
-#[serde(rename_all = "kebab-case")]
 pub struct BtnConfig {
-    #[serde(flatten)]
+    #[config(flatten)]
     pub size: CommonSize,
-    #[serde(default = "dt_color")]
-    #[serde(deserialize_with = "color_translate")]
-    #[schemars(schema_with = "schema_color")]
+    #[config(color, default = dt_color())]
     pub color: Color,
-    #[serde(default = "dt_border_width")]
+    #[config(default = dt_border_width())]
     pub border_width: i32,
-    #[serde(default = "dt_border_color")]
-    #[serde(deserialize_with = "color_translate")]
-    #[schemars(schema_with = "schema_color")]
+    #[config(color, default = dt_border_color())]
     pub border_color: Color,
-    #[serde(default)]
+    #[config(node, default)]
     pub event_map: KeyEventMap,
 }
 
-impl<S: knus::traits::ErrorSpan> knus::Decode<S> for BtnConfig {
-    fn decode_node(
-        node: &knus::ast::SpannedNode<S>,
-        ctx: &mut knus::decode::Context<S>,
-    ) -> Result<Self, knus::errors::DecodeError<S>> {
-        let size = CommonSize::decode_node(node, ctx)?;
-
-        let mut color = dt_color();
-        let mut border_width = dt_border_width();
-        let mut border_color = dt_border_color();
-        let mut event_map = KeyEventMap::default();
-
-        for child in node.children() {
-            match child.node_name.as_ref() {
-                "color" => {
-                    color = parse_color(&argv_str(child, ctx)?).to_kdl_error(child)?;
-                }
-                "border-width" => {
-                    border_width = argv_v(child, ctx)?;
-                }
-                "border-color" => {
-                    border_color = parse_color(&argv_str(child, ctx)?).to_kdl_error(child)?;
-                }
-                "event-map" => {
-                    event_map = KeyEventMap::decode_node(child, ctx)?;
-                }
-                _ => {}
-            }
-        }
-
-        Ok(Self {
-            size,
-            color,
-            border_width,
-            border_color,
-            event_map,
-        })
-    }
-}
-
 fn dt_color() -> Color {
     parse_color("#7B98FF").unwrap()
 }
@@ -85,6 +37,7 @@ fn dt_border_color() -> Color {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::def::{action::Action, shared::KeyBinding};
     // use knus::Decode;
 
     #[test]
@@ -128,16 +81,125 @@ btn {
             assert_eq!(btn.widget.border_color, dt_border_color());
             assert_eq!(btn.widget.event_map.len(), 2);
             assert_eq!(
-                btn.widget.event_map.get(&0x110),
-                Some(&"some command".to_string())
+                btn.widget.event_map.get_actions(&KeyBinding::from(0x110)),
+                Some(&[Action::Shell("some command".to_string())][..])
+            );
+            assert_eq!(
+                btn.widget.event_map.get_actions(&KeyBinding::from(0x111)),
+                Some(&[Action::Shell("another command".to_string())][..])
+            );
+        } else {
+            panic!("Expected Btn");
+        }
+    }
+
+    #[test]
+    fn test_decode_btn_config_with_gestures() {
+        let kdl = r#"
+btn {
+    edge "bottom"
+    thickness 20
+    length "40%"
+    event-map {
+        mouse-left "click"
+        double-mouse-left "double"
+        shift+long-kc-274 "shift long"
+    }
+}
+"#;
+        let parsed: Vec<crate::def::WidgetConf> = knus::parse("test", kdl).unwrap();
+        assert!(parsed[0].event_maps().iter().any(|m| m.uses_modifiers()));
+        if let crate::def::WidgetConf::Btn(btn) = &parsed[0] {
+            assert_eq!(btn.widget.event_map.len(), 3);
+        } else {
+            panic!("Expected Btn");
+        }
+    }
+
+    #[test]
+    fn test_decode_btn_config_with_scroll() {
+        let kdl = r#"
+btn {
+    edge "bottom"
+    thickness 20
+    length "40%"
+    event-map {
+        scroll-up "up" threshold=30
+        on-enter "preview"
+    }
+}
+"#;
+        let parsed: Vec<crate::def::WidgetConf> = knus::parse("test", kdl).unwrap();
+        if let crate::def::WidgetConf::Btn(btn) = &parsed[0] {
+            assert_eq!(btn.widget.event_map.len(), 2);
+        } else {
+            panic!("Expected Btn");
+        }
+
+        let kdl = kdl.replace("scroll-up", "mouse-left");
+        assert!(knus::parse::<Vec<crate::def::WidgetConf>>("test", &kdl).is_err());
+    }
+
+    #[test]
+    fn test_decode_btn_config_with_actions() {
+        let kdl = r#"
+btn {
+    edge "bottom"
+    thickness 20
+    length "40%"
+    event-map {
+        mouse-left {
+            action "toggle-pin" "audio"
+            action "pop" "clock" 2000
+            shell "notify-send pinned"
+        }
+        mouse-right {
+            shell "notify-send single"
+        }
+        scroll-up threshold=30 {
+            action "switch-workspace" 3
+        }
+    }
+}
+"#;
+        use crate::def::shared::SCROLL_UP;
+        let parsed: Vec<crate::def::WidgetConf> = knus::parse("test", kdl).unwrap();
+        if let crate::def::WidgetConf::Btn(btn) = &parsed[0] {
+            let map = &btn.widget.event_map;
+            assert_eq!(
+                map.get_actions(&KeyBinding::from(0x110)).unwrap(),
+                [
+                    Action::TogglePin("audio".to_string()),
+                    Action::Pop {
+                        namespace: "clock".to_string(),
+                        duration: 2000
+                    },
+                    Action::Shell("notify-send pinned".to_string()),
+                ]
             );
             assert_eq!(
-                btn.widget.event_map.get(&0x111),
-                Some(&"another command".to_string())
+                map.get_actions(&KeyBinding::from(0x111)).unwrap(),
+                [Action::Shell("notify-send single".to_string())]
+            );
+            assert_eq!(
+                map.get_actions(&KeyBinding::from(SCROLL_UP)).unwrap(),
+                [Action::SwitchWorkspace(3)]
             );
         } else {
             panic!("Expected Btn");
         }
+
+        for invalid in [
+            r#"mouse-left "cmd" { action "reload"; }"#,
+            r#"mouse-left { action "pin" "audio"; }"#,
+            r#"mouse-left { action "reload" "now"; }"#,
+            r#"mouse-left { run "cmd"; }"#,
+        ] {
+            let kdl = format!(
+                r#"btn {{ edge "bottom"; thickness 20; length "40%"; event-map {{ {invalid} }} }}"#
+            );
+            assert!(knus::parse::<Vec<crate::def::WidgetConf>>("test", &kdl).is_err());
+        }
     }
 
     #[test]
@@ -1,56 +1,38 @@
 use super::preset::Preset;
-use crate::def::shared::{
-    color_translate, option_color_translate, schema_color, schema_optional_color, CommonSize,
-};
-use crate::def::util::{argv_str, argv_v, ToKdlError};
+use crate::def::shared::CommonSize;
 use cosmic_text::Color;
-use knus::Decode;
-use schemars::JsonSchema;
 use schemars::Schema;
-use serde::Deserialize;
 use serde_json::Value;
 use util::color::parse_color;
-use way_edges_derive::{const_property, GetSize};
+use way_edges_derive::{const_property, Config, GetSize};
 
-#[derive(Debug, GetSize, Clone, Deserialize, JsonSchema)]
+#[derive(Debug, GetSize, Clone, Config)]
 #[schemars(deny_unknown_fields)]
 #[schemars(transform = SlideConfig_generate_defs)]
 #[const_property("type", "slider")]
-#[serde(rename_all = "kebab-case")]
 pub struct SlideConfig {
-    #[serde(flatten)]
+    #[config(flatten)]
     pub size: CommonSize,
-    #[serde(default = "dt_border_width")]
+    #[config(default = dt_border_width())]
     pub border_width: i32,
-    #[serde(default = "dt_obtuse_angle")]
+    #[config(default = dt_obtuse_angle())]
     pub obtuse_angle: f64,
-    #[serde(default = "dt_radius")]
+    #[config(default = dt_radius())]
     pub radius: f64,
-    #[serde(default = "dt_bg_color")]
-    #[serde(deserialize_with = "color_translate")]
-    #[schemars(schema_with = "schema_color")]
+    #[config(color, default = dt_bg_color())]
     pub bg_color: Color,
-    #[serde(default = "dt_fg_color")]
-    #[serde(deserialize_with = "color_translate")]
-    #[schemars(schema_with = "schema_color")]
+    #[config(color, default = dt_fg_color())]
     pub fg_color: Color,
-    #[serde(default = "dt_border_color")]
-    #[serde(deserialize_with = "color_translate")]
-    #[schemars(schema_with = "schema_color")]
+    #[config(color, default = dt_border_color())]
     pub border_color: Color,
-    #[serde(default)]
-    #[serde(deserialize_with = "option_color_translate")]
-    #[schemars(schema_with = "schema_optional_color")]
+    #[config(color)]
     pub fg_text_color: Option<Color>,
-    #[serde(default)]
-    #[serde(deserialize_with = "option_color_translate")]
-    #[schemars(schema_with = "schema_optional_color")]
+    #[config(color)]
     pub bg_text_color: Option<Color>,
-    #[serde(default)]
     pub redraw_only_on_internal_update: bool,
-    #[serde(default = "default_scroll_unit")]
+    #[config(default = default_scroll_unit())]
     pub scroll_unit: f64,
-    #[serde(default)]
+    #[config(node, default)]
     pub preset: Preset,
 }
 
@@ -77,85 +59,9 @@ fn dt_radius() -> f64 {
     20.
 }
 
-impl<S: knus::traits::ErrorSpan> Decode<S> for SlideConfig {
-    fn decode_node(
-        node: &knus::ast::SpannedNode<S>,
-        ctx: &mut knus::decode::Context<S>,
-    ) -> Result<Self, knus::errors::DecodeError<S>> {
-        let size = CommonSize::decode_node(node, ctx)?;
-
-        let mut border_width = dt_border_width();
-        let mut obtuse_angle = dt_obtuse_angle();
-        let mut radius = dt_radius();
-        let mut bg_color = dt_bg_color();
-        let mut fg_color = dt_fg_color();
-        let mut border_color = dt_border_color();
-        let mut fg_text_color = None;
-        let mut bg_text_color = None;
-        let mut redraw_only_on_internal_update = false;
-        let mut scroll_unit = default_scroll_unit();
-        let mut preset = Preset::default();
-
-        for child in node.children() {
-            match child.node_name.as_ref() {
-                "border-width" => {
-                    border_width = argv_v(child, ctx)?;
-                }
-                "obtuse-angle" => {
-                    obtuse_angle = argv_v(child, ctx)?;
-                }
-                "radius" => {
-                    radius = argv_v(child, ctx)?;
-                }
-                "bg-color" => {
-                    bg_color = parse_color(&argv_str(child, ctx)?).to_kdl_error(child)?;
-                }
-                "fg-color" => {
-                    fg_color = parse_color(&argv_str(child, ctx)?).to_kdl_error(child)?;
-                }
-                "border-color" => {
-                    border_color = parse_color(&argv_str(child, ctx)?).to_kdl_error(child)?;
-                }
-                "fg-text-color" => {
-                    fg_text_color = Some(parse_color(&argv_str(child, ctx)?).to_kdl_error(child)?);
-                }
-                "bg-text-color" => {
-                    bg_text_color = Some(parse_color(&argv_str(child, ctx)?).to_kdl_error(child)?);
-                }
-                "redraw-only-on-internal-update" => {
-                    redraw_only_on_internal_update = true;
-                }
-                "scroll-unit" => {
-                    scroll_unit = argv_v(child, ctx)?;
-                }
-                "preset" => {
-                    preset = Preset::decode_node(child, ctx)?;
-                }
-                _ => {}
-            }
-        }
-
-        Ok(Self {
-            size,
-            border_width,
-            obtuse_angle,
-            radius,
-            bg_color,
-            fg_color,
-            border_color,
-            fg_text_color,
-            bg_text_color,
-            redraw_only_on_internal_update,
-            scroll_unit,
-            preset,
-        })
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
-    use knus::Decode;
 
     #[test]
     fn test_decode_minimal_slider_config() {
@@ -264,6 +170,36 @@ slider {
         }
     }
 
+    #[test]
+    fn test_decode_slide_config_with_preset_ipc() {
+        let kdl = r##"
+slider {
+    edge "bottom"
+    thickness 20
+    length "40%"
+    preset "ipc" {
+        initial-value 0.5
+        pop-on-set
+        pop-duration 1500
+        on-change-command "mpc volume {float:0,100}"
+    }
+}
+"##;
+        let parsed: Vec<crate::def::WidgetConf> = knus::parse("test", kdl).unwrap();
+        if let crate::def::WidgetConf::Slider(slider) = &parsed[0] {
+            if let Preset::Ipc(conf) = &slider.widget.preset {
+                assert_eq!(conf.initial_value, 0.5);
+                assert!(conf.pop_on_set);
+                assert_eq!(conf.pop_duration, 1500);
+                assert!(conf.on_change_command.is_some());
+            } else {
+                panic!("Expected ipc preset");
+            }
+        } else {
+            panic!("Expected Slider");
+        }
+    }
+
     #[test]
     fn test_decode_slide_config_invalid_bg_color() {
         let kdl = r#"
@@ -1,81 +1,39 @@
 use cosmic_text::Color;
-use knus::Decode;
-use schemars::JsonSchema;
-use serde::Deserialize;
 use serde::Deserializer;
 use util::{
-    color::{parse_color, COLOR_BLACK},
+    color::COLOR_BLACK,
     template::{
         arg::TemplateArgFloatProcesser,
         base::{Template, TemplateProcesser},
     },
 };
+use way_edges_derive::Config;
 
-use crate::def::{
-    shared::{
-        color_translate, option_color_translate, schema_color, schema_optional_color,
-        schema_optional_template, Curve, KeyEventMap,
-    },
-    util::{argv_str, parse_optional_color},
-};
+use crate::def::shared::{Curve, KeyEventMap};
 
-#[derive(Debug, Clone, JsonSchema, Deserialize)]
-#[serde(rename_all = "kebab-case", tag = "type")]
+#[derive(Debug, Clone, Config)]
 pub enum Preset {
     Speaker(PulseAudioConfig),
     Microphone(PulseAudioConfig),
     Backlight(BacklightConfig),
     Custom(CustomConfig),
+    Ipc(IpcConfig),
 }
 impl Default for Preset {
     fn default() -> Self {
         Self::Custom(CustomConfig::default())
     }
 }
-impl<S: knus::traits::ErrorSpan> knus::Decode<S> for Preset {
-    fn decode_node(
-        node: &knus::ast::SpannedNode<S>,
-        ctx: &mut knus::decode::Context<S>,
-    ) -> Result<Self, knus::errors::DecodeError<S>> {
-        match argv_str(node, ctx)?.as_ref() {
-            "speaker" => Ok(Self::Speaker(PulseAudioConfig::decode_node(node, ctx)?)),
-            "microphone" => Ok(Self::Microphone(PulseAudioConfig::decode_node(node, ctx)?)),
-            "backlight" => Ok(Self::Backlight(BacklightConfig::decode_node(node, ctx)?)),
-            "custom" => Ok(Self::Custom(CustomConfig::decode_node(node, ctx)?)),
-            name => Err(knus::errors::DecodeError::unexpected(
-                node,
-                "preset type",
-                format!("unexpected preset type: {name}"),
-            )),
-        }
-    }
-}
 
-#[derive(Debug, Decode, Clone, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Config)]
 #[schemars(deny_unknown_fields)]
-#[serde(rename_all = "kebab-case")]
 pub struct PulseAudioConfig {
-    #[knus(
-        child,
-        default = default_mute_color(),
-        unwrap(argument, decode_with = parse_color)
-    )]
-    #[serde(default = "default_mute_color", deserialize_with = "color_translate")]
-    #[schemars(schema_with = "schema_color")]
+    #[config(color, default = default_mute_color())]
     pub mute_color: Color,
-    #[knus(child, default,
-        unwrap(argument, decode_with = parse_optional_color)
-    )]
-    #[serde(default, deserialize_with = "option_color_translate")]
-    #[schemars(schema_with = "schema_optional_color")]
+    #[config(color)]
     pub mute_text_color: Option<Color>,
-
-    #[knus(child, default, unwrap(argument))]
-    #[serde(default)]
+    #[config(default)]
     pub animation_curve: Curve,
-
-    #[knus(child, default, unwrap(argument))]
-    #[serde(default)]
     pub device: Option<String>,
 }
 
@@ -83,37 +41,54 @@ fn default_mute_color() -> Color {
     COLOR_BLACK
 }
 
-#[derive(Debug, Clone, Decode, Deserialize, JsonSchema, Default)]
+#[derive(Debug, Clone, Config, Default)]
 #[schemars(deny_unknown_fields)]
-#[serde(rename_all = "kebab-case")]
 pub struct BacklightConfig {
-    #[knus(child, default, unwrap(argument))]
-    #[serde(default)]
     pub device: Option<String>,
 }
 
-#[derive(Debug, Default, Clone, Decode, Deserialize, JsonSchema)]
+#[derive(Debug, Default, Clone, Config)]
 #[schemars(deny_unknown_fields)]
-#[serde(rename_all = "kebab-case")]
 pub struct CustomConfig {
-    #[knus(child, default, unwrap(argument))]
-    #[serde(default)]
+    #[config(default)]
     pub update_command: String,
-    #[knus(child, default, unwrap(argument))]
-    #[serde(default)]
+    #[config(default)]
     pub update_interval: u64,
 
-    #[knus(child, default, unwrap(argument, decode_with = slide_change_optional_template))]
-    #[serde(default)]
+    #[config(decode_with = slide_change_optional_template)]
+    #[serde(deserialize_with = "slide_change_template")]
+    #[serde(serialize_with = "crate::def::shared::serialize_optional_template")]
+    #[schemars(schema_with = "crate::def::shared::schema_optional_template")]
+    pub on_change_command: Option<Template>,
+
+    #[config(node, default)]
+    pub event_map: KeyEventMap,
+}
+
+#[derive(Debug, Clone, Config)]
+#[schemars(deny_unknown_fields)]
+pub struct IpcConfig {
+    #[config(default)]
+    pub initial_value: f64,
+
+    pub pop_on_set: bool,
+    #[config(default = dt_pop_duration())]
+    pub pop_duration: u64,
+
+    #[config(decode_with = slide_change_optional_template)]
     #[serde(deserialize_with = "slide_change_template")]
-    #[schemars(schema_with = "schema_optional_template")]
+    #[serde(serialize_with = "crate::def::shared::serialize_optional_template")]
+    #[schemars(schema_with = "crate::def::shared::schema_optional_template")]
     pub on_change_command: Option<Template>,
 
-    #[knus(child, default)]
-    #[serde(default)]
+    #[config(node, default)]
     pub event_map: KeyEventMap,
 }
 
+fn dt_pop_duration() -> u64 {
+    1000
+}
+
 fn slide_change_optional_template(s: &str) -> Result<Option<Template>, String> {
     if s.is_empty() {
         Ok(None)
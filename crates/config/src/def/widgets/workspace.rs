@@ -1,163 +1,46 @@
 use cosmic_text::Color;
-use knus::{Decode, DecodeScalar};
-use schemars::json_schema;
-use schemars::JsonSchema;
 use schemars::Schema;
-use serde::Deserialize;
-use serde::Deserializer;
 use serde_json::Value;
 use util::color::parse_color;
-use way_edges_derive::const_property;
-use way_edges_derive::GetSize;
+use way_edges_derive::{const_property, Config, GetSize};
 
-use crate::def::shared::{
-    color_translate, option_color_translate, schema_color, schema_optional_color, CommonSize, Curve,
-};
-use crate::def::util::{argv, argv_str, argv_v, ToKdlError};
+use crate::def::shared::{CommonSize, Curve};
 
-#[derive(Debug, GetSize, Clone, Deserialize, JsonSchema)]
+#[derive(Debug, GetSize, Clone, Config)]
 #[schemars(deny_unknown_fields)]
 #[schemars(transform = WorkspaceConfig_generate_defs)]
 #[const_property("type", "workspace")]
-#[serde(rename_all = "kebab-case")]
 pub struct WorkspaceConfig {
-    #[serde(flatten)]
+    #[config(flatten)]
     pub size: CommonSize,
-    #[serde(default = "dt_gap")]
+    #[config(default = dt_gap())]
     pub gap: i32,
-    #[serde(default = "dt_active_increase")]
+    #[config(default = dt_active_increase())]
     pub active_increase: f64,
-    #[serde(default = "dt_workspace_transition_duration")]
+    #[config(default = dt_workspace_transition_duration())]
     pub workspace_transition_duration: u64,
-    #[serde(default)]
+    #[config(default)]
     pub workspace_animation_curve: Curve,
-    #[serde(default = "dt_pop_duration")]
+    #[config(default = dt_pop_duration())]
     pub pop_duration: u64,
-    #[serde(default = "dt_default_color")]
-    #[serde(deserialize_with = "color_translate")]
-    #[schemars(schema_with = "schema_color")]
+    #[config(color, default = dt_default_color())]
     pub default_color: Color,
-    #[serde(default = "dt_focus_color")]
-    #[serde(deserialize_with = "color_translate")]
-    #[schemars(schema_with = "schema_color")]
+    #[config(color, default = dt_focus_color())]
     pub focus_color: Color,
-    #[serde(default = "dt_active_color")]
-    #[serde(deserialize_with = "color_translate")]
-    #[schemars(schema_with = "schema_color")]
+    #[config(color, default = dt_active_color())]
     pub active_color: Color,
-    #[serde(default)]
-    #[serde(deserialize_with = "option_color_translate")]
-    #[schemars(schema_with = "schema_optional_color")]
+    #[config(color)]
     pub hover_color: Option<Color>,
-    #[serde(default)]
     pub invert_direction: bool,
-    #[serde(default)]
     pub output_name: Option<String>,
-    #[serde(default)]
     pub focused_only: bool,
-    #[serde(default)]
     pub border_width: Option<i32>,
-    #[serde(default = "dt_border_radius")]
+    #[config(default = dt_border_radius())]
     pub border_radius: i32,
+    #[config(node, default = WorkspacePreset::Hyprland)]
     pub preset: WorkspacePreset,
 }
 
-impl<S: knus::traits::ErrorSpan> knus::Decode<S> for WorkspaceConfig {
-    fn decode_node(
-        node: &knus::ast::SpannedNode<S>,
-        ctx: &mut knus::decode::Context<S>,
-    ) -> Result<Self, knus::errors::DecodeError<S>> {
-        let size = CommonSize::decode_node(node, ctx)?;
-
-        let mut gap = dt_gap();
-        let mut active_increase = dt_active_increase();
-        let mut workspace_transition_duration = dt_workspace_transition_duration();
-        let mut workspace_animation_curve = Curve::default();
-        let mut pop_duration = dt_pop_duration();
-        let mut default_color = dt_default_color();
-        let mut focus_color = dt_focus_color();
-        let mut active_color = dt_active_color();
-        let mut hover_color = None;
-        let mut invert_direction = false;
-        let mut output_name = None;
-        let mut focused_only = false;
-        let mut border_width = None;
-        let mut border_radius = dt_border_radius();
-
-        let mut preset = WorkspacePreset::Hyprland;
-
-        for child in node.children() {
-            match child.node_name.as_ref() {
-                "gap" => {
-                    gap = argv_v(child, ctx)?;
-                }
-                "active-increase" => {
-                    active_increase = argv_v(child, ctx)?;
-                }
-                "workspace-transition-duration" => {
-                    workspace_transition_duration = argv_v(child, ctx)?;
-                }
-                "workspace-animation-curve" => {
-                    workspace_animation_curve = Curve::decode(argv(child)?, ctx)?;
-                }
-                "pop-duration" => {
-                    pop_duration = argv_v(child, ctx)?;
-                }
-                "default-color" => {
-                    default_color = parse_color(&argv_str(child, ctx)?).to_kdl_error(child)?;
-                }
-                "focus-color" => {
-                    focus_color = parse_color(&argv_str(child, ctx)?).to_kdl_error(child)?;
-                }
-                "active-color" => {
-                    active_color = parse_color(&argv_str(child, ctx)?).to_kdl_error(child)?;
-                }
-                "hover-color" => {
-                    hover_color = Some(parse_color(&argv_str(child, ctx)?).to_kdl_error(child)?);
-                }
-                "invert-direction" => {
-                    invert_direction = true;
-                }
-                "output-name" => {
-                    output_name = Some(argv_str(child, ctx)?);
-                }
-                "focused-only" => {
-                    focused_only = true;
-                }
-                "border-width" => {
-                    border_width = Some(argv_v(child, ctx)?);
-                }
-                "border-radius" => {
-                    border_radius = argv_v(child, ctx)?;
-                }
-                "preset" => {
-                    preset = WorkspacePreset::decode_node(child, ctx)?;
-                }
-                _ => {}
-            }
-        }
-
-        Ok(Self {
-            size,
-            gap,
-            active_increase,
-            workspace_transition_duration,
-            workspace_animation_curve,
-            pop_duration,
-            default_color,
-            focus_color,
-            active_color,
-            hover_color,
-            invert_direction,
-            output_name,
-            focused_only,
-            border_width,
-            border_radius,
-            preset,
-        })
-    }
-}
-
 fn dt_border_radius() -> i32 {
     5
 }
@@ -185,93 +68,20 @@ fn dt_active_color() -> Color {
     parse_color("#aaa").unwrap()
 }
 
-#[derive(Debug, Clone, JsonSchema)]
-#[schemars(transform = WorkspacePreset_generate_defs)]
-#[serde(rename_all = "kebab-case")]
+#[derive(Debug, Clone, Config)]
 pub enum WorkspacePreset {
     Hyprland,
     Niri(NiriConf),
 }
 
-impl<S: knus::traits::ErrorSpan> knus::Decode<S> for WorkspacePreset {
-    fn decode_node(
-        node: &knus::ast::SpannedNode<S>,
-        ctx: &mut knus::decode::Context<S>,
-    ) -> Result<Self, knus::errors::DecodeError<S>> {
-        match argv_str(node, ctx)?.as_str() {
-            "hyprland" => Ok(Self::Hyprland),
-            "niri" => Ok(Self::Niri(NiriConf::decode_node(node, ctx)?)),
-            other => Err(knus::errors::DecodeError::unexpected(
-                node,
-                "preset type",
-                format!("unknown workspace preset: {other}"),
-            )),
-        }
-    }
-}
-impl<'de> Deserialize<'de> for WorkspacePreset {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        let value = Value::deserialize(deserializer)?;
-
-        if let Some(preset_str) = value.as_str() {
-            match preset_str {
-                "hyprland" => Ok(WorkspacePreset::Hyprland),
-                "niri" => Ok(WorkspacePreset::Niri(NiriConf::default())),
-                _ => Err(serde::de::Error::unknown_variant(
-                    preset_str,
-                    &["hyprland", "niri"],
-                )),
-            }
-        } else {
-            #[derive(Deserialize)]
-            #[serde(rename_all = "kebab-case", tag = "type")]
-            enum Helper {
-                Hyprland,
-                Niri(NiriConf),
-            }
-
-            let helper: Helper = Helper::deserialize(value).map_err(|err| {
-                serde::de::Error::custom(format!("Failed to deserialize as object: {}", err))
-            })?;
-
-            match helper {
-                Helper::Hyprland => Ok(WorkspacePreset::Hyprland),
-                Helper::Niri(conf) => Ok(WorkspacePreset::Niri(conf)),
-            }
-        }
-    }
-}
-
-#[derive(Debug, Decode, Clone, Deserialize, JsonSchema, Default)]
+#[derive(Debug, Clone, Config, Default)]
 #[schemars(deny_unknown_fields)]
 #[schemars(transform = NiriConf_generate_defs)]
 #[const_property("type", "niri")]
-#[serde(rename_all = "kebab-case")]
 pub struct NiriConf {
-    #[knus(child)]
-    #[serde(default)]
     pub preserve_empty: bool,
 }
 
-#[allow(non_snake_case)]
-fn WorkspacePreset_generate_defs(s: &mut Schema) {
-    *s = json_schema!({
-      "oneOf": [
-      {
-          "type": "string",
-          "enum": ["hyprland", "niri"]
-      },
-      {
-        "type": "object",
-        "$ref": "#/$defs/NiriConf",
-      }
-      ]
-    })
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
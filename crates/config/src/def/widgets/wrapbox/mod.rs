@@ -10,11 +10,10 @@ use tray::TrayConfig;
 use util::color::parse_color;
 
 // Add serde imports
-use crate::def::shared::{color_translate, schema_color};
 use schemars::{JsonSchema, Schema};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use way_edges_derive::const_property;
+use way_edges_derive::{const_property, Config};
 
 // =================================== OUTLOOK
 fn dt_outlook_margin() -> NumMargins {
@@ -25,22 +24,15 @@ fn dt_outlook_margin() -> NumMargins {
         bottom: 5,
     }
 }
-#[derive(Debug, Decode, Deserialize, JsonSchema, Clone)]
-#[serde(rename_all = "kebab-case")]
+#[derive(Debug, Config, Clone)]
 pub struct OutlookWindowConfig {
-    #[knus(child, default = dt_outlook_margin())]
-    #[serde(default = "dt_outlook_margin")]
+    #[config(node, default = dt_outlook_margin())]
     pub margins: NumMargins,
-    #[knus(child, default = dt_color(), unwrap(argument, decode_with = parse_color))]
-    #[serde(default = "dt_color")]
-    #[serde(deserialize_with = "color_translate")]
-    #[schemars(schema_with = "schema_color")]
+    #[config(color, default = dt_color())]
     pub color: Color,
-    #[knus(child, default = dt_radius(), unwrap(argument))]
-    #[serde(default = "dt_radius")]
+    #[config(default = dt_radius())]
     pub border_radius: i32,
-    #[knus(child, default = dt_border_width(), unwrap(argument))]
-    #[serde(default = "dt_border_width")]
+    #[config(default = dt_border_width())]
     pub border_width: i32,
 }
 impl Default for OutlookWindowConfig {
@@ -63,23 +55,17 @@ fn dt_border_width() -> i32 {
     15
 }
 
-#[derive(Debug, Decode, Deserialize, JsonSchema, Clone)]
-#[serde(rename_all = "kebab-case")]
+#[derive(Debug, Config, Clone)]
 pub struct OutlookBoardConfig {
-    #[knus(child, default = dt_outlook_margin())]
-    #[serde(default = "dt_outlook_margin")]
+    #[config(node, default = dt_outlook_margin())]
     pub margins: NumMargins,
-    #[knus(child, default = dt_color(), unwrap(argument, decode_with = parse_color))]
-    #[serde(default = "dt_color")]
-    #[serde(deserialize_with = "color_translate")]
-    #[schemars(schema_with = "schema_color")]
+    #[config(color, default = dt_color())]
     pub color: Color,
-    #[knus(child, default = dt_radius(), unwrap(argument))]
-    #[serde(default = "dt_radius")]
+    #[config(default = dt_radius())]
     pub border_radius: i32,
 }
 
-#[derive(Debug, Deserialize, Clone, JsonSchema)]
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
 #[serde(rename_all = "kebab-case", tag = "type")]
 pub enum Outlook {
     Window(OutlookWindowConfig),
@@ -108,7 +94,7 @@ impl<S: knus::traits::ErrorSpan> knus::Decode<S> for Outlook {
 }
 
 // =================================== GRID
-#[derive(Debug, Default, Clone, Copy, DecodeScalar, Deserialize, JsonSchema)]
+#[derive(Debug, Default, Clone, Copy, DecodeScalar, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub enum Align {
     #[default]
@@ -182,7 +168,7 @@ impl Align {
 }
 
 // =================================== WIDGETS
-#[derive(Debug, Deserialize, JsonSchema, Clone)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
 #[serde(rename_all = "kebab-case", tag = "type")]
 pub enum BoxedWidget {
     Ring(RingConfig),
@@ -196,9 +182,9 @@ impl<S: knus::traits::ErrorSpan> knus::Decode<S> for BoxedWidget {
         ctx: &mut knus::decode::Context<S>,
     ) -> Result<Self, knus::errors::DecodeError<S>> {
         let widget = match argv_str(node, ctx)?.as_ref() {
-            "ring" => Self::Ring(RingConfig::decode_node(node, ctx)?),
-            "text" => Self::Text(TextConfig::decode_node(node, ctx)?),
-            "tray" => Self::Tray(TrayConfig::decode_node(node, ctx)?),
+            "ring" => Self::Ring(RingConfig::decode_known(node, ctx)?),
+            "text" => Self::Text(TextConfig::decode_known(node, ctx)?),
+            "tray" => Self::Tray(TrayConfig::decode_known(node, ctx)?),
             name => {
                 return Err(knus::errors::DecodeError::unexpected(
                     &node.node_name,
@@ -211,12 +197,15 @@ impl<S: knus::traits::ErrorSpan> knus::Decode<S> for BoxedWidget {
     }
 }
 
-#[derive(Debug, Deserialize, JsonSchema, Clone)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
 #[schemars(deny_unknown_fields)]
 #[serde(rename_all = "kebab-case")]
 pub struct BoxedWidgetConfig {
     #[serde(default = "dt_index")]
     pub index: [isize; 2],
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
     #[serde(flatten)]
     pub widget: BoxedWidget,
 }
@@ -231,40 +220,46 @@ impl<S: knus::traits::ErrorSpan> knus::Decode<S> for BoxedWidgetConfig {
     ) -> Result<Self, knus::errors::DecodeError<S>> {
         let widget = BoxedWidget::decode_node(node, ctx)?;
         let mut index = dt_index();
+        let mut id = None;
 
         for child in node.children() {
-            if child.node_name.as_ref() == "index" {
-                index = [argvi_v(child, ctx, 0)?, argvi_v(child, ctx, 1)?]
+            match child.node_name.as_ref() {
+                "index" => index = [argvi_v(child, ctx, 0)?, argvi_v(child, ctx, 1)?],
+                "id" => id = Some(argv_str(child, ctx)?),
+                _ => {}
             }
         }
 
-        Ok(Self { index, widget })
+        let known = match &widget {
+            BoxedWidget::Ring(_) => RingConfig::KDL_CHILDREN,
+            BoxedWidget::Text(_) => TextConfig::KDL_CHILDREN,
+            BoxedWidget::Tray(_) => TrayConfig::KDL_CHILDREN,
+        };
+        unknown::check_children(node, &[&["index", "id"], known], ctx);
+
+        Ok(Self { index, id, widget })
     }
 }
 
 use crate::def::{
     shared::NumMargins,
+    unknown,
     util::{argv_str, argvi_v},
 };
 
 // =================================== FINAL
-#[derive(Debug, Decode, Deserialize, JsonSchema, Clone)]
+#[derive(Debug, Config, Clone)]
 #[schemars(deny_unknown_fields)]
 #[schemars(transform = BoxConfig_generate_defs)]
 #[const_property("type", "wrap-box")]
-#[serde(rename_all = "kebab-case")]
 pub struct BoxConfig {
-    #[knus(child, default)]
-    #[serde(default)]
+    #[config(node, default)]
     pub outlook: Outlook,
-    #[knus(child, default = dt_gap(), unwrap(argument))]
-    #[serde(default = "dt_gap")]
+    #[config(default = dt_gap())]
     pub gap: f64,
-    #[knus(child, default, unwrap(argument))]
-    #[serde(default)]
+    #[config(default)]
     pub align: Align,
-    #[knus(children(name = "item"), default)]
-    #[serde(default)]
+    #[config(children = "item")]
     pub items: Vec<BoxedWidgetConfig>,
 }
 fn dt_gap() -> f64 {
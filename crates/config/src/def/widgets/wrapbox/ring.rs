@@ -1,21 +1,23 @@
 use cosmic_text::{Color, FamilyOwned};
-use knus::Decode;
 use schemars::JsonSchema;
-use serde::{Deserialize, Deserializer};
+use serde::{Deserialize, Deserializer, Serialize};
 use util::color::parse_color;
 use util::template::{
     arg::{TemplateArgFloatProcesser, TemplateArgRingPresetProcesser},
     base::{Template, TemplateProcesser},
 };
 
+use crate::def::unknown;
 use crate::def::util::{argv_str, argv_v};
 
 use crate::def::shared::{
-    color_translate, deserialize_family_owned, dt_family_owned, parse_family_owned, schema_color,
-    schema_family_owned, schema_optional_template, Curve, KeyEventMap,
+    deserialize_family_owned, dt_family_owned, parse_family_owned, schema_family_owned,
+    schema_optional_template, serialize_family_owned, serialize_optional_template, Curve,
+    KeyEventMap,
 };
+use way_edges_derive::Config;
 
-#[derive(Debug, Deserialize, JsonSchema, Clone)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
 #[serde(
     rename_all = "kebab-case",
     rename_all_fields = "kebab-case",
@@ -35,6 +37,7 @@ pub enum RingPreset {
         #[serde(default = "dt_update_interval")]
         update_interval: u64,
         #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
         core: Option<usize>,
     },
     Battery {
@@ -52,6 +55,10 @@ pub enum RingPreset {
         update_interval: u64,
         cmd: String,
     },
+    Ipc {
+        #[serde(default)]
+        initial_value: f64,
+    },
 }
 impl Default for RingPreset {
     fn default() -> Self {
@@ -77,7 +84,6 @@ impl<S: knus::traits::ErrorSpan> knus::Decode<S> for RingPreset {
         let mut partition = dt_partition();
         let mut cmd = String::default();
 
-        #[allow(clippy::single_match)]
         match argv_str(node, ctx)?.as_ref() {
             "ram" => {
                 for child in node.children() {
@@ -85,7 +91,7 @@ impl<S: knus::traits::ErrorSpan> knus::Decode<S> for RingPreset {
                         "update-interval" => {
                             update_interval = argv_v(child, ctx)?;
                         }
-                        _ => {}
+                        _ => unknown::unknown_node(child, &["update-interval"], ctx),
                     }
                 }
                 Ok(Self::Ram { update_interval })
@@ -96,7 +102,7 @@ impl<S: knus::traits::ErrorSpan> knus::Decode<S> for RingPreset {
                         "update-interval" => {
                             update_interval = argv_v(child, ctx)?;
                         }
-                        _ => {}
+                        _ => unknown::unknown_node(child, &["update-interval"], ctx),
                     }
                 }
                 Ok(Self::Swap { update_interval })
@@ -111,7 +117,7 @@ impl<S: knus::traits::ErrorSpan> knus::Decode<S> for RingPreset {
                         "core" => {
                             core = Some(argv_v(child, ctx)?);
                         }
-                        _ => {}
+                        _ => unknown::unknown_node(child, &["update-interval", "core"], ctx),
                     }
                 }
                 Ok(Self::Cpu { update_interval, core })
@@ -122,7 +128,7 @@ impl<S: knus::traits::ErrorSpan> knus::Decode<S> for RingPreset {
                         "update-interval" => {
                             update_interval = argv_v(child, ctx)?;
                         }
-                        _ => {}
+                        _ => unknown::unknown_node(child, &["update-interval"], ctx),
                     }
                 }
                 Ok(Self::Battery { update_interval })
@@ -136,7 +142,7 @@ impl<S: knus::traits::ErrorSpan> knus::Decode<S> for RingPreset {
                         "partition" => {
                             partition = argv_str(child, ctx)?;
                         }
-                        _ => {}
+                        _ => unknown::unknown_node(child, &["update-interval", "partition"], ctx),
                     }
                 }
                 Ok(Self::Disk { update_interval, partition })
@@ -150,88 +156,84 @@ impl<S: knus::traits::ErrorSpan> knus::Decode<S> for RingPreset {
                         "cmd" => {
                             cmd = argv_str(child, ctx)?;
                         }
-                        _ => {}
+                        _ => unknown::unknown_node(child, &["update-interval", "cmd"], ctx),
                     }
                 }
                 Ok(Self::Custom { update_interval, cmd })
             }
+            "ipc" => {
+                let mut initial_value = 0.;
+                for child in node.children() {
+                    match child.node_name.as_ref() {
+                        "initial-value" => {
+                            initial_value = argv_v(child, ctx)?;
+                        }
+                        _ => unknown::unknown_node(child, &["initial-value"], ctx),
+                    }
+                }
+                Ok(Self::Ipc { initial_value })
+            }
 
             _ => Err(knus::errors::DecodeError::unexpected(
                 &node.node_name,
-                "\"ram\", \"swap\", \"cpu\", \"battery\", \"disk\" or \"custom\"",
-                "RingPreset node should be one of \"ram\", \"swap\", \"cpu\", \"battery\", \"disk\" or \"custom\"",
+                "\"ram\", \"swap\", \"cpu\", \"battery\", \"disk\", \"custom\" or \"ipc\"",
+                "RingPreset node should be one of \"ram\", \"swap\", \"cpu\", \"battery\", \"disk\", \"custom\" or \"ipc\"",
             )),
         }
     }
 }
 
-#[derive(Debug, Decode, Clone, Deserialize, JsonSchema)]
-#[serde(rename_all = "kebab-case")]
+#[derive(Debug, Clone, Config)]
 #[schemars(deny_unknown_fields)]
 pub struct RingConfig {
-    #[knus(child, default = dt_r(), unwrap(argument))]
-    #[serde(default = "dt_r")]
+    #[config(default = dt_r())]
     pub radius: i32,
 
-    #[knus(child, default = dt_rw(), unwrap(argument))]
-    #[serde(default = "dt_rw")]
+    #[config(default = dt_rw())]
     pub ring_width: i32,
 
-    #[knus(child, default = dt_bg(), unwrap(argument, decode_with = parse_color))]
-    #[serde(default = "dt_bg")]
-    #[serde(deserialize_with = "color_translate")]
-    #[schemars(schema_with = "schema_color")]
+    #[config(color, default = dt_bg())]
     pub bg_color: Color,
 
-    #[knus(child, default = dt_fg(), unwrap(argument, decode_with = parse_color))]
-    #[serde(default = "dt_fg")]
-    #[serde(deserialize_with = "color_translate")]
-    #[schemars(schema_with = "schema_color")]
+    #[config(color, default = dt_fg())]
     pub fg_color: Color,
 
-    #[knus(child, default = dt_tt(), unwrap(argument))]
-    #[serde(default = "dt_tt")]
+    #[config(default = dt_tt())]
     pub text_transition_ms: u64,
 
-    #[knus(child, default, unwrap(argument))]
-    #[serde(default)]
+    #[config(default)]
     pub animation_curve: Curve,
 
-    #[knus(child, default, unwrap(argument, decode_with = ring_text_optional_template))]
-    #[serde(default)]
+    #[config(decode_with = ring_text_optional_template, default)]
     #[serde(deserialize_with = "ring_text_template")]
+    #[serde(serialize_with = "serialize_optional_template")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     #[schemars(schema_with = "schema_optional_template")]
     pub prefix: Option<Template>,
 
-    #[knus(child)]
-    #[serde(default)]
     pub prefix_hide: bool,
 
-    #[knus(child, default, unwrap(argument, decode_with = ring_text_optional_template))]
-    #[serde(default)]
+    #[config(decode_with = ring_text_optional_template, default)]
     #[serde(deserialize_with = "ring_text_template")]
+    #[serde(serialize_with = "serialize_optional_template")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     #[schemars(schema_with = "schema_optional_template")]
     pub suffix: Option<Template>,
 
-    #[knus(child)]
-    #[serde(default)]
     pub suffix_hide: bool,
 
-    #[knus(child, default = dt_family_owned(), unwrap(argument, decode_with = parse_family_owned))]
-    #[serde(default = "dt_family_owned")]
+    #[config(decode_with = parse_family_owned, default = dt_family_owned())]
     #[serde(deserialize_with = "deserialize_family_owned")]
+    #[serde(serialize_with = "serialize_family_owned")]
     #[schemars(schema_with = "schema_family_owned")]
     pub font_family: FamilyOwned,
 
-    #[knus(child, default, unwrap(argument))]
-    #[serde(default)]
     pub font_size: Option<i32>,
 
-    #[knus(child, default)]
-    #[serde(default)]
+    #[config(node, default)]
     pub event_map: KeyEventMap,
 
-    #[knus(child)]
+    #[config(node)]
     pub preset: RingPreset,
 }
 
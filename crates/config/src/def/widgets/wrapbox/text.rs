@@ -1,18 +1,19 @@
 use cosmic_text::{Color, FamilyOwned};
-use knus::Decode;
 use schemars::JsonSchema;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use util::color::{parse_color, COLOR_BLACK};
 
 use crate::def::{
     shared::{
-        color_translate, deserialize_family_owned, dt_family_owned, parse_family_owned,
-        schema_color, schema_family_owned, KeyEventMap,
+        deserialize_family_owned, dt_family_owned, parse_family_owned, schema_family_owned,
+        serialize_family_owned, KeyEventMap,
     },
+    unknown,
     util::{argv_str, argv_v},
 };
+use way_edges_derive::Config;
 
-#[derive(Debug, Deserialize, JsonSchema, Clone)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
 #[serde(
     rename_all = "kebab-case",
     rename_all_fields = "kebab-case",
@@ -24,6 +25,7 @@ pub enum TextPreset {
         #[serde(default = "dt_time_format")]
         format: String,
         #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
         time_zone: Option<String>,
         #[serde(default = "dt_update_interval")]
         update_interval: u64,
@@ -33,6 +35,10 @@ pub enum TextPreset {
         update_interval: u64,
         cmd: String,
     },
+    Ipc {
+        #[serde(default)]
+        initial_text: String,
+    },
 }
 impl Default for TextPreset {
     fn default() -> Self {
@@ -66,7 +72,11 @@ impl<S: knus::traits::ErrorSpan> knus::Decode<S> for TextPreset {
                         "update-interval" => {
                             update_interval = argv_v(child, ctx)?;
                         }
-                        _ => {}
+                        _ => unknown::unknown_node(
+                            child,
+                            &["format", "time-zone", "update-interval"],
+                            ctx,
+                        ),
                     }
                 }
                 Ok(Self::Time {
@@ -84,7 +94,7 @@ impl<S: knus::traits::ErrorSpan> knus::Decode<S> for TextPreset {
                         "cmd" => {
                             cmd = argv_str(child, ctx)?;
                         }
-                        _ => {}
+                        _ => unknown::unknown_node(child, &["update-interval", "cmd"], ctx),
                     }
                 }
                 Ok(Self::Custom {
@@ -92,11 +102,23 @@ impl<S: knus::traits::ErrorSpan> knus::Decode<S> for TextPreset {
                     cmd,
                 })
             }
+            "ipc" => {
+                let mut initial_text = String::default();
+                for child in node.children() {
+                    match child.node_name.as_ref() {
+                        "initial-text" => {
+                            initial_text = argv_str(child, ctx)?;
+                        }
+                        _ => unknown::unknown_node(child, &["initial-text"], ctx),
+                    }
+                }
+                Ok(Self::Ipc { initial_text })
+            }
 
             _ => Err(knus::errors::DecodeError::unexpected(
                 &node.node_name,
-                "\"time\" or \"custom\"",
-                "TextPreset node should be \"time\" or \"custom\"",
+                "\"time\", \"custom\" or \"ipc\"",
+                "TextPreset node should be \"time\", \"custom\" or \"ipc\"",
             )),
         }
     }
@@ -108,31 +130,25 @@ fn dt_update_interval() -> u64 {
     1000
 }
 
-#[derive(Debug, Decode, Clone, Deserialize, JsonSchema)]
-#[serde(rename_all = "kebab-case")]
+#[derive(Debug, Clone, Config)]
 #[schemars(deny_unknown_fields)]
 pub struct TextConfig {
-    #[knus(child, default = dt_fg_color(), unwrap(argument, decode_with = parse_color))]
-    #[serde(default = "dt_fg_color")]
-    #[serde(deserialize_with = "color_translate")]
-    #[schemars(schema_with = "schema_color")]
+    #[config(color, default = dt_fg_color())]
     pub fg_color: Color,
 
-    #[knus(child, default = dt_font_size(), unwrap(argument))]
-    #[serde(default = "dt_font_size")]
+    #[config(default = dt_font_size())]
     pub font_size: i32,
 
-    #[knus(child, default = dt_family_owned(), unwrap(argument, decode_with = parse_family_owned))]
-    #[serde(default = "dt_family_owned")]
+    #[config(decode_with = parse_family_owned, default = dt_family_owned())]
     #[serde(deserialize_with = "deserialize_family_owned")]
+    #[serde(serialize_with = "serialize_family_owned")]
     #[schemars(schema_with = "schema_family_owned")]
     pub font_family: FamilyOwned,
 
-    #[knus(child, default)]
-    #[serde(default)]
+    #[config(node, default)]
     pub event_map: KeyEventMap,
 
-    #[knus(child)]
+    #[config(node)]
     pub preset: TextPreset,
 }
 
@@ -253,4 +269,34 @@ wrap-box {
             panic!("Expected WrapBox");
         }
     }
+
+    #[test]
+    fn test_decode_text_ipc_preset() {
+        let kdl = r##"
+wrap-box {
+    edge "bottom"
+    thickness 20
+    length "40%"
+    item "text" {
+        id "clock-text"
+        preset "ipc" {
+            initial-text "hello"
+        }
+    }
+}
+"##;
+        let parsed: Vec<crate::def::WidgetConf> = knus::parse("test", kdl).unwrap();
+        let crate::def::WidgetConf::WrapBox(wrap_box) = &parsed[0] else {
+            panic!("Expected WrapBox");
+        };
+        let item = &wrap_box.widget.items[0];
+        assert_eq!(item.id.as_deref(), Some("clock-text"));
+        let crate::def::widgets::wrapbox::BoxedWidget::Text(text_config) = &item.widget else {
+            panic!("Expected Text widget");
+        };
+        match &text_config.preset {
+            TextPreset::Ipc { initial_text } => assert_eq!(initial_text, "hello"),
+            _ => panic!("Expected Ipc preset"),
+        }
+    }
 }
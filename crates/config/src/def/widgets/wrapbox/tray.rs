@@ -1,20 +1,18 @@
 use cosmic_text::{Color, FamilyOwned};
-use knus::{Decode, DecodeScalar};
+use knus::DecodeScalar;
 use schemars::JsonSchema;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use util::color::{parse_color, COLOR_WHITE};
 
-use crate::def::{
-    shared::{
-        color_translate, deserialize_family_owned, dt_family_owned, option_color_translate,
-        parse_family_owned, schema_color, schema_family_owned, schema_optional_color, NumMargins,
-    },
-    util::parse_optional_color,
+use crate::def::shared::{
+    deserialize_family_owned, dt_family_owned, parse_family_owned, schema_family_owned,
+    serialize_family_owned, NumMargins,
 };
+use way_edges_derive::Config;
 
 use super::Align;
 
-#[derive(Debug, Default, Clone, DecodeScalar, Deserialize, JsonSchema)]
+#[derive(Debug, Default, Clone, DecodeScalar, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub enum HeaderMenuStack {
     #[default]
@@ -22,7 +20,7 @@ pub enum HeaderMenuStack {
     MenuTop,
 }
 
-#[derive(Debug, Default, Clone, DecodeScalar, Deserialize, JsonSchema)]
+#[derive(Debug, Default, Clone, DecodeScalar, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub enum HeaderMenuAlign {
     #[default]
@@ -38,17 +36,12 @@ impl HeaderMenuAlign {
     }
 }
 
-#[derive(Debug, Decode, Clone, Deserialize, JsonSchema)]
-#[serde(rename_all = "kebab-case")]
+#[derive(Debug, Clone, Config)]
 #[schemars(deny_unknown_fields)]
 pub struct HeaderDrawConfig {
-    #[knus(child, default = dt_header_font_pixel_height(), unwrap(argument))]
-    #[serde(default = "dt_header_font_pixel_height")]
+    #[config(default = dt_header_font_pixel_height())]
     pub font_pixel_height: i32,
-    #[knus(child, default = dt_header_text_color(), unwrap(argument, decode_with = parse_color))]
-    #[serde(default = "dt_header_text_color")]
-    #[serde(deserialize_with = "color_translate")]
-    #[schemars(schema_with = "schema_color")]
+    #[config(color, default = dt_header_text_color())]
     pub text_color: Color,
 }
 impl Default for HeaderDrawConfig {
@@ -66,39 +59,24 @@ fn dt_header_text_color() -> Color {
     COLOR_WHITE
 }
 
-#[derive(Debug, Clone, Decode, Deserialize, JsonSchema)]
-#[serde(rename_all = "kebab-case")]
+#[derive(Debug, Clone, Config)]
 #[schemars(deny_unknown_fields)]
 pub struct MenuDrawConfig {
-    #[knus(child, default = dt_menu_margin())]
-    #[serde(default = "dt_menu_margin")]
+    #[config(node, default = dt_menu_margin())]
     pub margin: NumMargins,
-    #[knus(child, default = dt_font_pixel_height(), unwrap(argument))]
-    #[serde(default = "dt_font_pixel_height")]
+    #[config(default = dt_font_pixel_height())]
     pub font_pixel_height: i32,
-    #[knus(child, default = dt_menu_icon_size(), unwrap(argument))]
-    #[serde(default = "dt_menu_icon_size")]
+    #[config(default = dt_menu_icon_size())]
     pub icon_size: i32,
-    #[knus(child, default = dt_menu_marker_size(), unwrap(argument))]
-    #[serde(default = "dt_menu_marker_size")]
+    #[config(default = dt_menu_marker_size())]
     pub marker_size: i32,
-    #[knus(child, default = dt_menu_separator_height(), unwrap(argument))]
-    #[serde(default = "dt_menu_separator_height")]
+    #[config(default = dt_menu_separator_height())]
     pub separator_height: i32,
-    #[knus(child, default = dt_menu_border_color(), unwrap(argument, decode_with = parse_color))]
-    #[serde(default = "dt_menu_border_color")]
-    #[serde(deserialize_with = "color_translate")]
-    #[schemars(schema_with = "schema_color")]
+    #[config(color, default = dt_menu_border_color())]
     pub border_color: Color,
-    #[knus(child, default = dt_menu_text_color(), unwrap(argument, decode_with = parse_color))]
-    #[serde(default = "dt_menu_text_color")]
-    #[serde(deserialize_with = "color_translate")]
-    #[schemars(schema_with = "schema_color")]
+    #[config(color, default = dt_menu_text_color())]
     pub text_color: Color,
-    #[knus(child, default, unwrap(argument, decode_with = parse_optional_color))]
-    #[serde(default)]
-    #[serde(deserialize_with = "option_color_translate")]
-    #[schemars(schema_with = "schema_optional_color")]
+    #[config(color)]
     pub marker_color: Option<Color>,
 }
 impl Default for MenuDrawConfig {
@@ -142,40 +120,30 @@ fn dt_menu_text_color() -> Color {
     COLOR_WHITE
 }
 
-#[derive(Debug, Decode, Clone, Deserialize, JsonSchema)]
-#[serde(rename_all = "kebab-case")]
+#[derive(Debug, Clone, Config)]
 #[schemars(deny_unknown_fields)]
 pub struct TrayConfig {
-    #[knus(child, default = dt_family_owned(), unwrap(argument, decode_with = parse_family_owned))]
-    #[serde(default = "dt_family_owned")]
+    #[config(decode_with = parse_family_owned, default = dt_family_owned())]
     #[serde(deserialize_with = "deserialize_family_owned")]
+    #[serde(serialize_with = "serialize_family_owned")]
     #[schemars(schema_with = "schema_family_owned")]
     pub font_family: FamilyOwned,
-    #[knus(child, default, unwrap(argument))]
-    #[serde(default)]
     pub icon_theme: Option<String>,
-    #[knus(child, default = dt_icon_size(), unwrap(argument))]
-    #[serde(default = "dt_icon_size")]
+    #[config(default = dt_icon_size())]
     pub icon_size: i32,
-    #[knus(child, default = dt_tray_gap(), unwrap(argument))]
-    #[serde(default = "dt_tray_gap")]
+    #[config(default = dt_tray_gap())]
     pub tray_gap: i32,
-    #[knus(child, default, unwrap(argument))]
-    #[serde(default)]
+    #[config(default)]
     pub grid_align: Align,
 
-    #[knus(child, default, unwrap(argument))]
-    #[serde(default)]
+    #[config(default)]
     pub header_menu_stack: HeaderMenuStack,
-    #[knus(child, default, unwrap(argument))]
-    #[serde(default)]
+    #[config(default)]
     pub header_menu_align: HeaderMenuAlign,
 
-    #[knus(child, default)]
-    #[serde(default)]
+    #[config(node, default)]
     pub header_draw_config: HeaderDrawConfig,
-    #[knus(child, default)]
-    #[serde(default)]
+    #[config(node, default)]
     pub menu_draw_config: MenuDrawConfig,
 }
 impl Default for TrayConfig {
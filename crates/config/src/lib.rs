@@ -1,16 +1,20 @@
+mod check;
+mod convert;
 pub mod def;
+mod load;
 // mod serde;
 
 use std::{
-    fs::OpenOptions,
-    io::Read,
     path::{Path, PathBuf},
-    sync::OnceLock,
+    sync::{Mutex, OnceLock},
 };
 
 use schemars::schema_for;
 
-use crate::def::{parse_jsonc, parse_kdl, Root};
+pub use check::{check_config, validate};
+pub use convert::{convert_config, fmt_losses, write_config, ConfigFormat};
+
+use crate::{def::Root, load::Loader};
 
 static CONFIG_PATH: OnceLock<PathBuf> = OnceLock::new();
 
@@ -48,52 +52,23 @@ pub fn get_config_path() -> &'static Path {
     CONFIG_PATH.get().unwrap().as_path()
 }
 
-enum ConfigContent {
-    Serde(String),
-    Kdl(String),
-    Unknown(String),
-}
-
-fn get_config_file_content() -> Result<ConfigContent, String> {
-    let p = get_config_path();
+// main config file and the files it includes, as of the last load
+static CONFIG_FILES: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
 
-    let content = OpenOptions::new()
-        .read(true)
-        .open(p)
-        .and_then(|mut f| {
-            let mut s = String::new();
-            f.read_to_string(&mut s).map(|_| s)
-        })
-        .map_err(|e| format!("failed to open config file: {e}"))?;
-
-    if let Some(ext) = p.extension().and_then(|e| e.to_str()) {
-        match ext {
-            "kdl" => Ok(ConfigContent::Kdl(content)),
-            "json" | "jsonc" => Ok(ConfigContent::Serde(content)),
-            _ => {
-                log::warn!("unsupported config file extension: {ext:?}");
-                Ok(ConfigContent::Unknown(content))
-            }
-        }
-    } else {
-        Ok(ConfigContent::Unknown(content))
-    }
+pub fn get_config() -> Result<Root, String> {
+    let mut loader = Loader::default();
+    let res = loader.load(get_config_path());
+    *CONFIG_FILES.lock().unwrap() = loader.files;
+    res
 }
 
-pub fn get_config() -> Result<Root, String> {
-    match get_config_file_content()? {
-        ConfigContent::Serde(c) => parse_jsonc(&c),
-        ConfigContent::Kdl(c) => parse_kdl(&c),
-        ConfigContent::Unknown(c) => {
-            // try kdl first
-            parse_kdl(&c)
-                .or_else(|e| {
-                    log::warn!("failed to parse config file as KDL: {e}");
-                    // try serde next
-                    parse_jsonc(&c)
-                })
-                .inspect_err(|e| log::error!("failed to parse config file as KDL or JSON: {e}"))
-        }
+/// files to watch for changes, the main config file and every included file.
+pub fn get_config_files() -> Vec<PathBuf> {
+    let files = CONFIG_FILES.lock().unwrap();
+    if files.is_empty() {
+        vec![get_config_path().to_path_buf()]
+    } else {
+        files.clone()
     }
 }
 
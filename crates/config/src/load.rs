@@ -0,0 +1,466 @@
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    fs::OpenOptions,
+    io::Read,
+    mem,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use miette::{Diagnostic, LabeledSpan, MietteDiagnostic, NamedSource};
+
+use crate::def::{self, unknown::line_column, Includes, LoadContext, Root};
+
+fn read_config_file(p: &Path) -> Result<String, String> {
+    OpenOptions::new()
+        .read(true)
+        .open(p)
+        .and_then(|mut f| {
+            let mut s = String::new();
+            f.read_to_string(&mut s).map(|_| s)
+        })
+        .map_err(|e| format!("failed to open config file {}: {e}", p.display()))
+}
+
+// a kdl file within the text every kdl file of a load is decoded from
+struct KdlPart {
+    start: usize,
+    name: String,
+    text: String,
+}
+
+/// the files of a load, kdl files are decoded together so themes, templates and defaults
+/// reach every file wherever they're declared, json files go after them.
+#[derive(Default)]
+struct Sources {
+    kdl: String,
+    parts: Vec<KdlPart>,
+    // file name, text
+    json: Vec<(String, String)>,
+}
+impl Sources {
+    fn of(name: String, text: String) -> Self {
+        Self {
+            parts: vec![KdlPart {
+                start: 0,
+                name,
+                text,
+            }],
+            ..Default::default()
+        }
+    }
+
+    /// read a file and return its `include`s.
+    fn add(&mut self, p: &Path) -> Result<Vec<String>, String> {
+        let name = p.display().to_string();
+        let text = read_config_file(p)?;
+        let kdl = match p.extension().and_then(|e| e.to_str()) {
+            Some("kdl") => Some(true),
+            Some("json" | "jsonc") => Some(false),
+            ext => {
+                if let Some(ext) = ext {
+                    log::warn!("unsupported config file extension: {ext:?}");
+                }
+                None
+            }
+        };
+
+        match kdl {
+            Some(true) => self.add_kdl(name, text),
+            Some(false) => self.add_json(name, text),
+            // try kdl first
+            None => self
+                .add_kdl(name.clone(), text.clone())
+                .or_else(|e| {
+                    log::warn!("failed to parse config file as KDL: {e}");
+                    self.add_json(name, text)
+                })
+                .inspect_err(|e| log::error!("failed to parse config file as KDL or JSON: {e}")),
+        }
+    }
+
+    fn add_kdl(&mut self, name: String, text: String) -> Result<Vec<String>, String> {
+        // syntax errors come with the file alone
+        let Includes(include) = match knus::parse::<Includes>(&name, &text) {
+            Ok(include) => include,
+            Err(e) => return Err(Sources::of(name, text).report(e).join("\n")),
+        };
+        if !self.kdl.is_empty() {
+            self.kdl.push('\n');
+        }
+        let start = self.kdl.len();
+        self.kdl.push_str(&text);
+        self.parts.push(KdlPart { start, name, text });
+        Ok(include)
+    }
+
+    fn add_json(&mut self, name: String, text: String) -> Result<Vec<String>, String> {
+        let value: serde_jsonrc::Value =
+            serde_jsonrc::from_str(&text).map_err(|e| format!("{name}: JSON parse error: {e}"))?;
+        let include = match value.get("include") {
+            Some(v) => serde_jsonrc::from_value(v.clone())
+                .map_err(|e| format!("{name}: include must be a list of paths: {e}"))?,
+            None => vec![],
+        };
+        self.json.push((name, text));
+        Ok(include)
+    }
+
+    /// every error found, one per problem.
+    fn decode(&self, load: &Rc<RefCell<LoadContext>>) -> Result<Root, Vec<String>> {
+        // json definitions first, kdl widgets can use the theme of them
+        let mut json = vec![];
+        for (name, text) in self.json.iter() {
+            let value = def::define_json(text, &mut load.borrow_mut())
+                .map_err(|e| vec![format!("{name}: {e}")])?;
+            json.push((name, text, value));
+        }
+
+        let mut root = Root {
+            include: vec![],
+            widgets: vec![],
+        };
+        if let Some(first) = self.parts.first() {
+            let res = def::parse_kdl(&first.name, &self.kdl, load);
+            let warnings = mem::take(&mut load.borrow_mut().warnings);
+            for (offset, msg) in warnings {
+                let (part, offset) = self.locate(offset);
+                let (line, column) = line_column(&part.text, offset);
+                log::warn!("{}:{line}:{column}: {msg}", part.name);
+            }
+            root = res.map_err(|e| self.report(e))?;
+        }
+
+        for (name, text, value) in json {
+            let sub = def::decode_json(text, value, &load.borrow())
+                .map_err(|e| vec![format!("{name}: {e}")])?;
+            root.include.extend(sub.include);
+            root.widgets.extend(sub.widgets);
+        }
+        Ok(root)
+    }
+
+    // the file an offset of the kdl text is in, and the offset within it
+    fn locate(&self, offset: usize) -> (&KdlPart, usize) {
+        let i = self.parts.partition_point(|p| p.start <= offset).max(1) - 1;
+        let part = &self.parts[i];
+        (part, offset - part.start)
+    }
+
+    // every error of the decode, shown with the file it is in
+    fn report(&self, e: knus::Error) -> Vec<String> {
+        let errors: Vec<_> = e
+            .related()
+            .into_iter()
+            .flatten()
+            .map(|d| self.render(d))
+            .collect();
+        if errors.is_empty() {
+            return vec![format!("{:?}", miette::Report::new(e))];
+        }
+        errors
+    }
+
+    fn render(&self, d: &dyn Diagnostic) -> String {
+        let labels: Vec<LabeledSpan> = d.labels().map(|l| l.collect()).unwrap_or_default();
+        let Some(first) = labels.first() else {
+            return d.to_string();
+        };
+        let (part, _) = self.locate(first.offset());
+        let labels = labels
+            .iter()
+            .filter(|l| self.locate(l.offset()).0.start == part.start)
+            .map(|l| {
+                LabeledSpan::new(
+                    l.label().map(String::from),
+                    l.offset() - part.start,
+                    l.len(),
+                )
+            });
+        let mut diagnostic = MietteDiagnostic::new(d.to_string()).with_labels(labels);
+        if let Some(help) = d.help() {
+            diagnostic = diagnostic.with_help(help.to_string());
+        }
+        let source = NamedSource::new(&part.name, part.text.clone());
+        format!(
+            "{:?}",
+            miette::Report::new(diagnostic).with_source_code(source)
+        )
+    }
+}
+
+/// one config file without the files it includes, `include` is kept as paths.
+pub(crate) fn parse_config_file(p: &Path) -> Result<Root, String> {
+    let mut sources = Sources::default();
+    let include = sources.add(p)?;
+    let mut root = sources.decode(&Rc::default()).map_err(|e| e.join("\n"))?;
+    root.include = include;
+    Ok(root)
+}
+
+/// loads a config file and the files it includes, recursively.
+#[derive(Debug, Default)]
+pub struct Loader {
+    // files being loaded, for cycle detection
+    stack: Vec<PathBuf>,
+    // canonical paths of the files loaded, a file included twice is loaded once
+    loaded: HashSet<PathBuf>,
+    seen: HashSet<PathBuf>,
+    /// every file touched, including the ones that failed to load.
+    pub files: Vec<PathBuf>,
+    /// skip included files that fail to load and collect their errors in `errors`.
+    pub keep_going: bool,
+    pub errors: Vec<String>,
+    /// unknown nodes are errors, like `strict` in the config.
+    pub strict: bool,
+}
+impl Loader {
+    pub fn load(&mut self, path: &Path) -> Result<Root, String> {
+        let mut sources = Sources::default();
+        self.collect(path, &mut sources)?;
+
+        let load = Rc::new(RefCell::new(LoadContext {
+            strict: self.strict,
+            ..Default::default()
+        }));
+        let mut root = match sources.decode(&load) {
+            Ok(root) => root,
+            // the first one is returned like any other error
+            Err(mut errors) if self.keep_going => {
+                let first = errors.remove(0);
+                self.errors.extend(errors);
+                return Err(first);
+            }
+            Err(errors) => return Err(errors.join("\n")),
+        };
+        root.include.clear();
+        Ok(root)
+    }
+
+    // read the file and the files it includes, depth first
+    fn collect(&mut self, path: &Path, sources: &mut Sources) -> Result<(), String> {
+        if self.seen.insert(path.to_path_buf()) {
+            self.files.push(path.to_path_buf());
+        }
+
+        let canonical = path
+            .canonicalize()
+            .map_err(|e| format!("failed to open config file {}: {e}", path.display()))?;
+        if let Some(i) = self.stack.iter().position(|p| p == &canonical) {
+            let chain = self.stack[i..]
+                .iter()
+                .chain([&canonical])
+                .map(|p| p.display().to_string())
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            return Err(format!("include cycle: {chain}"));
+        }
+        if !self.loaded.insert(canonical.clone()) {
+            return Ok(());
+        }
+
+        let include = sources.add(path)?;
+
+        // relative to the including file
+        let dir = path.parent().unwrap_or(Path::new("."));
+        self.stack.push(canonical);
+        for include in include {
+            if let Err(e) = self.collect(&dir.join(include), sources) {
+                let e = format!("{e}\n  included from {}", path.display());
+                if !self.keep_going {
+                    return Err(e);
+                }
+                self.errors.push(e);
+            }
+        }
+        self.stack.pop();
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("way-edges-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("widgets")).unwrap();
+        dir
+    }
+
+    const BTN: &str = r#"btn { edge "bottom"; thickness 20; length "40%"; }"#;
+
+    #[test]
+    fn test_load_include() {
+        let dir = temp_dir("include");
+        std::fs::write(
+            dir.join("config.kdl"),
+            format!("include \"widgets/a.kdl\" \"widgets/b.jsonc\"\n{BTN}"),
+        )
+        .unwrap();
+        std::fs::write(dir.join("widgets/a.kdl"), BTN).unwrap();
+        std::fs::write(
+            dir.join("widgets/b.jsonc"),
+            r#"{ "widgets": [{ "type": "btn", "edge": "top", "thickness": 20, "length": "40%" }] }"#,
+        )
+        .unwrap();
+
+        let mut loader = Loader::default();
+        let root = loader.load(&dir.join("config.kdl")).unwrap();
+        assert_eq!(root.widgets.len(), 3);
+        assert_eq!(loader.files.len(), 3);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_load_include_cycle() {
+        let dir = temp_dir("cycle");
+        std::fs::write(dir.join("config.kdl"), "include \"widgets/a.kdl\"").unwrap();
+        std::fs::write(dir.join("widgets/a.kdl"), "include \"../config.kdl\"").unwrap();
+
+        let mut loader = Loader::default();
+        let err = loader.load(&dir.join("config.kdl")).unwrap_err();
+        assert!(err.contains("include cycle"), "{err}");
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_load_include_twice() {
+        let dir = temp_dir("diamond");
+        std::fs::write(
+            dir.join("config.kdl"),
+            "include \"widgets/a.kdl\" \"widgets/b.kdl\"",
+        )
+        .unwrap();
+        std::fs::write(dir.join("widgets/a.kdl"), "include \"common.kdl\"").unwrap();
+        std::fs::write(dir.join("widgets/b.kdl"), "include \"./common.kdl\"").unwrap();
+        std::fs::write(dir.join("widgets/common.kdl"), BTN).unwrap();
+
+        let mut loader = Loader::default();
+        let root = loader.load(&dir.join("config.kdl")).unwrap();
+        assert_eq!(root.widgets.len(), 1);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_load_shared_definitions() {
+        use crate::def::WidgetConf;
+        use util::color::parse_color;
+
+        let dir = temp_dir("shared");
+        // the included file defines what the including file and an earlier include use
+        std::fs::write(
+            dir.join("config.kdl"),
+            "include \"widgets/a.kdl\" \"widgets/theme.kdl\"\nbtn { extends \"base\"; }",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("widgets/a.kdl"),
+            "btn { extends \"base\"; color \"$accent\"; }",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("widgets/theme.kdl"),
+            r##"
+theme {
+    accent "#ff0000"
+}
+template "base" {
+    edge "bottom"
+    thickness 20
+    length "40%"
+}
+defaults "btn" {
+    border-width 7
+}
+"##,
+        )
+        .unwrap();
+
+        let mut loader = Loader::default();
+        let root = loader.load(&dir.join("config.kdl")).unwrap();
+        let [WidgetConf::Btn(main), WidgetConf::Btn(a)] = &root.widgets[..] else {
+            panic!("Expected two Btn");
+        };
+        assert_eq!(main.widget.border_width, 7);
+        assert_eq!(a.widget.border_width, 7);
+        assert_eq!(a.widget.color, parse_color("#ff0000").unwrap());
+
+        // an include alone does not see what its siblings define
+        assert!(parse_config_file(&dir.join("widgets/a.kdl")).is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_load_foreign_template_error() {
+        let dir = temp_dir("foreign-template");
+        std::fs::write(
+            dir.join("config.kdl"),
+            "include \"widgets/base.kdl\"\n\n\nbtn { extends \"base\"; }",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("widgets/base.kdl"),
+            "// ünïcödé\ntemplate \"base\" {\n    edge \"bottom\"\n    thickness \"abc\"\n    length \"40%\"\n}",
+        )
+        .unwrap();
+
+        let mut loader = Loader::default();
+        let err = loader.load(&dir.join("config.kdl")).unwrap_err();
+        // reported against the file of the template
+        assert!(err.contains("base.kdl"), "{err}");
+        assert!(!err.contains("config.kdl"), "{err}");
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_load_every_error() {
+        let dir = temp_dir("every-error");
+        let broken = r#"btn { edge "bottom"; thickness "abc"; length "40%"; }"#;
+        std::fs::write(
+            dir.join("config.kdl"),
+            format!("include \"widgets/a.kdl\"\n{broken}"),
+        )
+        .unwrap();
+        std::fs::write(dir.join("widgets/a.kdl"), format!("{BTN}\n{broken}")).unwrap();
+
+        let mut loader = Loader {
+            keep_going: true,
+            ..Default::default()
+        };
+        let err = loader.load(&dir.join("config.kdl")).unwrap_err();
+        assert!(err.contains("config.kdl"), "{err}");
+        assert_eq!(loader.errors.len(), 1);
+        assert!(loader.errors[0].contains("a.kdl"), "{}", loader.errors[0]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_load_keep_going() {
+        let dir = temp_dir("keep-going");
+        std::fs::write(
+            dir.join("config.kdl"),
+            format!("include \"widgets/missing.kdl\" \"widgets/a.kdl\"\n{BTN}"),
+        )
+        .unwrap();
+        std::fs::write(dir.join("widgets/a.kdl"), BTN).unwrap();
+
+        let mut loader = Loader {
+            keep_going: true,
+            ..Default::default()
+        };
+        let root = loader.load(&dir.join("config.kdl")).unwrap();
+        assert_eq!(root.widgets.len(), 2);
+        assert_eq!(loader.errors.len(), 1);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
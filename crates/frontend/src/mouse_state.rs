@@ -1,14 +1,32 @@
+use config::def::shared::{Gesture, KeyBinding, KeyEventMap, Modifiers, ON_ENTER, ON_LEAVE};
 use smithay_client_toolkit::seat::pointer::{AxisScroll, PointerEvent, PointerEventKind};
 
+// ms, compared with the timestamps of the compositor
+const DOUBLE_CLICK_INTERVAL: u32 = 300;
+const LONG_PRESS_DURATION: u32 = 500;
+
 #[derive(Debug, Clone)]
 pub enum MouseEvent {
     Press((f64, f64), u32),
-    Release((f64, f64), u32),
+    // the gesture and modifiers are only known on release
+    Release((f64, f64), KeyBinding),
     Enter((f64, f64)),
     Leave,
     Motion((f64, f64)),
     Scroll(AxisScroll, AxisScroll), // horizontal, vertical
 }
+impl MouseEvent {
+    /// run what the event map binds to this event: releases, scrolling and hovering.
+    pub fn call_event_map(&self, event_map: &mut KeyEventMap) {
+        match self {
+            Self::Release(_, k) => event_map.call(*k),
+            Self::Enter(_) => event_map.call(ON_ENTER.into()),
+            Self::Leave => event_map.call(ON_LEAVE.into()),
+            Self::Scroll(h, v) => event_map.scroll(h.absolute, v.absolute),
+            _ => {}
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct MouseStateData {
@@ -33,6 +51,12 @@ impl Default for MouseStateData {
 pub struct MouseState {
     pub data: MouseStateData,
     mouse_debug: bool,
+
+    // time of the current press
+    pressed_at: u32,
+    // key and release time of the last click, for double click
+    last_click: Option<(u32, u32)>,
+    double_pressed: bool,
 }
 impl MouseState {
     pub fn is_hovering(&self) -> bool {
@@ -45,18 +69,25 @@ impl MouseState {
         Self {
             data: MouseStateData::new(),
             mouse_debug: false,
+            pressed_at: 0,
+            last_click: None,
+            double_pressed: false,
         }
     }
 
     #[allow(clippy::wrong_self_convention)]
-    pub fn from_wl_pointer(&mut self, event: &PointerEvent) -> Option<MouseEvent> {
+    pub fn from_wl_pointer(
+        &mut self,
+        event: &PointerEvent,
+        modifiers: Modifiers,
+    ) -> Option<MouseEvent> {
         use PointerEventKind::*;
         match event.kind {
             Enter { .. } => self.hover_enter(event.position),
             Leave { .. } => self.hover_leave(),
             Motion { .. } => self.hover_motion(event.position),
-            Press { button, .. } => self.press(button, event.position),
-            Release { button, .. } => self.unpress(button, event.position),
+            Press { button, time, .. } => self.press(button, time, event.position),
+            Release { button, time, .. } => self.unpress(button, time, modifiers, event.position),
             Axis {
                 horizontal,
                 vertical,
@@ -66,26 +97,54 @@ impl MouseState {
     }
 
     // triggers
-    fn press(&mut self, p: u32, pos: (f64, f64)) -> Option<MouseEvent> {
+    fn press(&mut self, p: u32, time: u32, pos: (f64, f64)) -> Option<MouseEvent> {
         if self.mouse_debug {
             log::debug!("Mouse Debug info: key pressed: {p}");
         };
 
         if self.data.pressing.is_none() {
             self.data.pressing = Some(p);
+            self.pressed_at = time;
+            self.double_pressed = self.last_click.is_some_and(|(key, released_at)| {
+                key == p && time.wrapping_sub(released_at) <= DOUBLE_CLICK_INTERVAL
+            });
             Some(MouseEvent::Press(pos, p))
         } else {
             None
         }
     }
-    fn unpress(&mut self, p: u32, pos: (f64, f64)) -> Option<MouseEvent> {
+    fn unpress(
+        &mut self,
+        p: u32,
+        time: u32,
+        modifiers: Modifiers,
+        pos: (f64, f64),
+    ) -> Option<MouseEvent> {
         if self.mouse_debug {
             log::debug!("Mouse Debug info: key released: {p}");
         };
 
         if self.data.pressing.eq(&Some(p)) {
             self.data.pressing = None;
-            Some(MouseEvent::Release(pos, p))
+            let gesture = if time.wrapping_sub(self.pressed_at) >= LONG_PRESS_DURATION {
+                self.last_click = None;
+                Gesture::Long
+            } else if self.double_pressed {
+                // a third click starts over
+                self.last_click = None;
+                Gesture::Double
+            } else {
+                self.last_click = Some((p, time));
+                Gesture::Click
+            };
+            Some(MouseEvent::Release(
+                pos,
+                KeyBinding {
+                    code: p,
+                    gesture,
+                    modifiers,
+                },
+            ))
         } else {
             None
         }
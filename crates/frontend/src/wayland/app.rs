@@ -1,20 +1,23 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     mem,
     rc::Rc,
     sync::{atomic::AtomicPtr, Arc, Mutex, Weak},
     time::Duration,
 };
 
-use backend::ipc::IPCCommand;
+use backend::config_file_watch::refresh_watched_files;
+use backend::ipc::{emit_event, IPCCommand, IPCEvent, IPCResponse, NamespacePattern, WidgetStatus};
 use calloop::{
     channel::Sender,
     ping::{make_ping, Ping},
     Idle, LoopHandle, LoopSignal,
 };
 use config::def::{
-    common::{CommonConfig, MonitorSpecifier},
-    shared::Curve,
+    action::Action,
+    common::{CommonConfig, MonitorSpecifier, OutputDesc},
+    shared::{Curve, KeyBinding, Modifiers},
+    when::Environment,
     WidgetConf,
 };
 use smithay_client_toolkit::{
@@ -30,13 +33,13 @@ use smithay_client_toolkit::{
     registry::{GlobalProxy, RegistryState},
     seat::{pointer::PointerEvent, SeatState},
     shell::{
-        wlr_layer::{LayerShell, LayerSurface},
+        wlr_layer::{Anchor, KeyboardInteractivity, LayerShell, LayerSurface},
         WaylandSurface,
     },
     shm::{slot::SlotPool, Shm},
 };
 use wayland_client::{
-    protocol::{wl_output::WlOutput, wl_pointer, wl_surface::WlSurface},
+    protocol::{wl_keyboard, wl_output::WlOutput, wl_pointer, wl_surface::WlSurface},
     Proxy, QueueHandle,
 };
 
@@ -52,7 +55,13 @@ use super::{draw::DrawCore, window_pop_state::WindowPopState};
 pub struct App {
     pub exit: bool,
     pub show_mouse_key: bool,
+    // send a desktop notification when reloading fails
+    pub notify_errors: bool,
     pub widget_map: WidgetMap,
+    // configs of the last successful load, `enable` builds widgets from here
+    pub widget_confs: Vec<WidgetConf>,
+    // namespaces disabled by ipc, kept across reloads
+    pub disabled: HashSet<String>,
 
     pub queue_handle: QueueHandle<App>,
     pub event_loop_handle: LoopHandle<'static, App>,
@@ -65,6 +74,9 @@ pub struct App {
     pub fractional_manager: GlobalProxy<WpFractionalScaleManagerV1>,
     pub viewporter_manager: GlobalProxy<WpViewporter>,
     pub pointer: Option<wl_pointer::WlPointer>,
+    pub keyboard: Option<wl_keyboard::WlKeyboard>,
+    // held on the keyboard, only known while a widget has keyboard focus
+    pub modifiers: Modifiers,
 
     pub shell: LayerShell,
     pub shm: Shm,
@@ -73,65 +85,471 @@ pub struct App {
     // if the the outputs get updated before we first initialize widgets, do not call reload
     pub(crate) first_time_initialized: bool,
     pub(crate) reload_guard: Option<Idle<'static>>,
+    // the pending reload has to rebuild every widget, outputs changed
+    pub(crate) full_reload_pending: bool,
 }
 impl App {
-    pub fn handle_ipc(&mut self, cmd: IPCCommand) {
+    pub fn handle_ipc(&mut self, cmd: IPCCommand) -> Result<IPCResponse, String> {
         match cmd {
             IPCCommand::TogglePin(wn) => self.toggle_pin(&wn),
-            IPCCommand::Exit => self.exit = true,
-            IPCCommand::Reload => self.reload(),
+            IPCCommand::Show(wn) => self.show(&wn),
+            IPCCommand::Hide(wn) => self.hide(&wn),
+            IPCCommand::Pop {
+                namespace,
+                duration,
+            } => self.pop(&namespace, duration),
+            IPCCommand::List => Ok(IPCResponse::ok().with_payload(self.list())),
+            IPCCommand::Set { namespace, value } => self.set(&namespace, value),
+            IPCCommand::Push {
+                namespace,
+                item,
+                value,
+                label,
+            } => self.push(&namespace, &item, &value, label.as_deref()),
+            IPCCommand::Trigger {
+                namespace,
+                item,
+                key,
+            } => self.trigger(&namespace, item.as_deref(), key),
+            IPCCommand::Disable(wn) => self.disable(&wn),
+            IPCCommand::Enable(wn) => self.enable(&wn),
+            IPCCommand::Exit => {
+                self.exit = true;
+                Ok(IPCResponse::ok().with_message("exiting"))
+            }
+            IPCCommand::Reload => {
+                // reload right away to reply with the result, a pending reload is covered by it
+                if let Some(old) = self.reload_guard.take() {
+                    old.cancel()
+                }
+                self.first_time_initialized = true;
+                let full = mem::take(&mut self.full_reload_pending);
+                let widgets = self.reload_widgets(full)?;
+                Ok(IPCResponse::ok().with_message(format!("reloaded {widgets} widget(s)")))
+            }
+        }
+    }
+
+    /// actions bound in event maps, sent here so the widget running them is not locked.
+    pub fn run_action(&mut self, action: Action) {
+        let res = match &action {
+            Action::TogglePin(wn) => self.toggle_pin(wn).map(|_| ()),
+            Action::Show(wn) => self.show(wn).map(|_| ()),
+            Action::Hide(wn) => self.hide(wn).map(|_| ()),
+            Action::Pop {
+                namespace,
+                duration,
+            } => self.pop(namespace, *duration).map(|_| ()),
+            Action::Set { namespace, value } => self.set(namespace, *value).map(|_| ()),
+            Action::Reload => {
+                self.reload_config();
+                Ok(())
+            }
+            Action::SwitchWorkspace(n) => {
+                // compositor ipc blocks, a slow compositor must not stall the event loop
+                let n = *n;
+                std::thread::spawn(move || {
+                    if let Err(e) = backend::workspace::focus_workspace(n) {
+                        log::warn!("Failed to switch to workspace {n}: {e}");
+                    }
+                });
+                Ok(())
+            }
+            Action::Shell(cmd) => {
+                util::shell::shell_cmd_non_block(cmd.clone());
+                Ok(())
+            }
         };
+        if let Err(e) = res {
+            log::warn!("Failed to run action {action:?}: {e}");
+        }
+    }
+
+    fn find_widgets(&self, name: &str) -> Result<Vec<Arc<Mutex<Widget>>>, String> {
+        let pattern = NamespacePattern::parse(name)?;
+        let ws = self.widget_map.get_widgets(&pattern);
+        if ws.is_empty() {
+            return Err(format!("No widget found with namespace: {name}"));
+        }
+        Ok(ws)
     }
 
-    fn toggle_pin(&mut self, name: &str) {
-        for w in self.widget_map.get_widgets(name) {
+    fn toggle_pin(&mut self, name: &str) -> Result<IPCResponse, String> {
+        let ws = self.find_widgets(name)?;
+        for w in ws.iter() {
             w.lock().unwrap().toggle_pin(self)
         }
+        Ok(IPCResponse::ok().with_message(format!("toggled pin of {} widget(s)", ws.len())))
+    }
+
+    fn show(&mut self, name: &str) -> Result<IPCResponse, String> {
+        let ws = self.find_widgets(name)?;
+        for w in ws.iter() {
+            w.lock().unwrap().show(self)
+        }
+        Ok(IPCResponse::ok().with_message(format!("showed {} widget(s)", ws.len())))
+    }
+
+    fn hide(&mut self, name: &str) -> Result<IPCResponse, String> {
+        let ws = self.find_widgets(name)?;
+        for w in ws.iter() {
+            w.lock().unwrap().hide(self)
+        }
+        Ok(IPCResponse::ok().with_message(format!("hid {} widget(s)", ws.len())))
+    }
+
+    fn pop(&mut self, name: &str, duration: u64) -> Result<IPCResponse, String> {
+        let ws = self.find_widgets(name)?;
+        for w in ws.iter() {
+            // lock is released before popping, `PopEssential` locks the widget itself
+            let layer = w.lock().unwrap().layer.clone();
+            PopEssential {
+                pop_duration: Duration::from_millis(duration),
+                layer,
+            }
+            .pop(self);
+        }
+        Ok(IPCResponse::ok().with_message(format!("popped {} widget(s)", ws.len())))
+    }
+
+    fn set(&mut self, name: &str, value: f64) -> Result<IPCResponse, String> {
+        let ws = self.find_widgets(name)?;
+        if !value.is_finite() {
+            return Err(format!("Invalid value: {value}"));
+        }
+        // all or nothing, a pattern matching other widgets changes none of them
+        for w in ws.iter() {
+            let w = w.lock().unwrap();
+            if !w.w.accepts_ipc_set() {
+                return Err(format!(
+                    "{} does not accept values, only slider with `ipc` preset does",
+                    w.namespace
+                ));
+            }
+        }
+        for w in ws.iter() {
+            let (pop, layer) = {
+                let mut wg = w.lock().unwrap();
+                let pop = wg.w.on_ipc_set(value)?;
+                wg.on_widget_update(self);
+                (pop, wg.layer.clone())
+            };
+            if let Some(duration) = pop {
+                PopEssential {
+                    pop_duration: Duration::from_millis(duration),
+                    layer,
+                }
+                .pop(self);
+            }
+        }
+        Ok(IPCResponse::ok().with_message(format!("set value of {} widget(s)", ws.len())))
+    }
+
+    fn push(
+        &mut self,
+        name: &str,
+        item: &str,
+        value: &str,
+        label: Option<&str>,
+    ) -> Result<IPCResponse, String> {
+        let ws = self.find_widgets(name)?;
+        for w in ws.iter() {
+            // redraw is done by the item's redraw channel
+            w.lock().unwrap().w.on_ipc_push(item, value, label)?;
+        }
+        Ok(IPCResponse::ok().with_message(format!("pushed to {} widget(s)", ws.len())))
+    }
+
+    fn trigger(
+        &mut self,
+        name: &str,
+        item: Option<&str>,
+        key: KeyBinding,
+    ) -> Result<IPCResponse, String> {
+        let pattern = NamespacePattern::parse(name)?;
+        // every output has its own widget with the same event map, run the command only once
+        let ws = self
+            .widget_map
+            .0
+            .iter()
+            .filter(|(ns, _)| pattern.matches(ns))
+            .filter_map(|(_, ws)| ws.first().cloned())
+            .collect::<Vec<_>>();
+        if ws.is_empty() {
+            return Err(format!("No widget found with namespace: {name}"));
+        }
+        for w in ws.iter() {
+            w.lock().unwrap().w.on_ipc_trigger(item, key)?;
+        }
+        Ok(IPCResponse::ok().with_message(format!("triggered {key} of {} widget(s)", ws.len())))
+    }
+
+    fn disable(&mut self, name: &str) -> Result<IPCResponse, String> {
+        let pattern = NamespacePattern::parse(name)?;
+        let names = self
+            .widget_confs
+            .iter()
+            .map(|c| &c.common().namespace)
+            .filter(|ns| pattern.matches(ns))
+            .cloned()
+            .collect::<HashSet<_>>();
+        if names.is_empty() {
+            return Err(format!("No widget found with namespace: {name}"));
+        }
+
+        let mut count = 0;
+        for ns in names {
+            if self.disabled.insert(ns.clone()) {
+                let ws = self.widget_map.0.remove(&ns).unwrap_or_default();
+                count += ws.len();
+                self.clear_widgets(ws);
+            }
+        }
+        Ok(IPCResponse::ok().with_message(format!("disabled {count} widget(s)")))
+    }
+
+    fn enable(&mut self, name: &str) -> Result<IPCResponse, String> {
+        let pattern = NamespacePattern::parse(name)?;
+        let names = self
+            .disabled
+            .iter()
+            .filter(|ns| pattern.matches(ns))
+            .cloned()
+            .collect::<Vec<_>>();
+        if names.is_empty() {
+            return Err(format!("No disabled widget found with namespace: {name}"));
+        }
+
+        let env = self.when_environment();
+        let mut count = 0;
+        for ns in names {
+            let confs = self
+                .widget_confs
+                .iter()
+                .filter(|c| c.common().namespace == ns && c.common().when.is_met(&env))
+                .cloned()
+                .collect::<Vec<_>>();
+
+            // build every widget of the namespace before enabling it
+            let focused = focused_output(&confs);
+            let mut ws = vec![];
+            for conf in confs.iter() {
+                match WidgetMap::init_widgets(conf, focused.as_deref(), self) {
+                    Ok(built) => ws.extend(built),
+                    Err(e) => {
+                        self.clear_widgets(ws);
+                        return Err(format!("Failed to enable {ns}, it stays disabled: {e}"));
+                    }
+                }
+            }
+            self.disabled.remove(&ns);
+            count += ws.len();
+            if !ws.is_empty() {
+                self.widget_map.0.insert(ns, ws);
+            }
+        }
+        Ok(IPCResponse::ok().with_message(format!("enabled {count} widget(s)")))
+    }
+
+    fn when_environment(&self) -> Environment<'static> {
+        Environment {
+            compositor: backend::workspace::running_compositor(),
+            output_count: self.output_state.outputs().count(),
+        }
     }
 
-    fn reload_widgets(&mut self) {
-        // clear contents of old widgets
-        let ws = mem::take(&mut self.widget_map.0)
-            .into_values()
+    fn list(&self) -> Vec<WidgetStatus> {
+        let mut list = self
+            .widget_map
+            .0
+            .values()
             .flatten()
+            .map(|w| w.lock().unwrap().status(&self.output_state))
             .collect::<Vec<_>>();
+        list.sort_by(|a, b| a.namespace.cmp(&b.namespace));
+        list
+    }
+
+    // without `full`, only widgets whose config changed are rebuilt,
+    // the others keep their pop state, animations and backends.
+    fn reload_widgets(&mut self, full: bool) -> Result<usize, String> {
+        emit_event(IPCEvent::ReloadStart);
+
+        // check the new config before touching the running widgets
+        let res = config::get_config().and_then(|c| {
+            let errors = config::validate(&c);
+            if errors.is_empty() {
+                Ok(c)
+            } else {
+                Err(errors.join("\n"))
+            }
+        });
+        // included files may have changed
+        refresh_watched_files();
+
+        let res = res.and_then(|c| {
+            let changed = (!full).then(|| changed_namespaces(&self.widget_confs, &c.widgets));
+            let rebuild = |conf: &&WidgetConf| match changed.as_ref() {
+                Some(changed) => changed.contains(&conf.common().namespace),
+                None => true,
+            };
+
+            // clear contents of old widgets
+            let ws: Vec<_> = match changed.as_ref() {
+                Some(changed) => changed
+                    .iter()
+                    .filter_map(|ns| self.widget_map.0.remove(ns))
+                    .flatten()
+                    .collect(),
+                None => mem::take(&mut self.widget_map.0)
+                    .into_values()
+                    .flatten()
+                    .collect(),
+            };
+            log::info!("Rebuilding {} widget(s)", ws.len());
+            self.clear_widgets(ws);
+
+            match WidgetMap::new(c.widgets.iter().filter(rebuild), self) {
+                Ok(map) => {
+                    self.widget_confs = c.widgets;
+                    self.widget_map.0.extend(map.0);
+                    Ok(())
+                }
+                Err(e) => {
+                    // bring back the previous ones
+                    match WidgetMap::new(self.widget_confs.iter().filter(rebuild), self) {
+                        Ok(map) => self.widget_map.0.extend(map.0),
+                        Err(e) => log::error!("Failed to restore previous widgets: {e}"),
+                    }
+                    Err(e)
+                }
+            }
+        });
+
+        let widgets = self.widget_map.0.values().map(Vec::len).sum();
+        let error = res.err();
+        if let Some(e) = error.as_ref() {
+            log::error!("Failed to reload widgets, previous widgets are kept: {e}");
+            if self.notify_errors {
+                notify_error(e);
+            }
+        }
+        emit_event(IPCEvent::ReloadFinish {
+            widgets,
+            error: error.clone(),
+        });
+
+        match error {
+            Some(e) => Err(e),
+            None => Ok(widgets),
+        }
+    }
+
+    fn clear_widgets(&mut self, ws: Vec<Arc<Mutex<Widget>>>) {
         ws.into_iter().for_each(|arc| {
             // we make sure that no other references exist
-            // tipically this should be Some() since this function is called in idle
+            // tipically this should be Some() since this function is called in the event loop
             // and the backend or any other threads shall not hold references to widgets
-            let mtx = Arc::into_inner(arc).unwrap();
+            let Some(mtx) = Arc::into_inner(arc) else {
+                log::error!("Failed to clear widget contents, widget is still referenced");
+                return;
+            };
 
             // and tipically this should be Ok() since no other references should exist
             match mtx.into_inner() {
                 Ok(mut w) => w.clear_contents(self),
                 Err(e) => {
                     log::error!(
-                        "Failed to clear widget contents during reload, mutex of this widget is poisoned: {e}"
+                        "Failed to clear widget contents, mutex of this widget is poisoned: {e}"
                     );
                 }
             }
         });
-
-        // create new
-        self.widget_map = config::get_config()
-            .and_then(|c| WidgetMap::new(c.widgets.clone(), self))
-            .unwrap_or_else(|e| {
-                log::error!("Failed to load widgets: {e}");
-                WidgetMap(HashMap::new())
-            });
     }
 
+    /// rebuild every widget, for output changes.
     pub fn reload(&mut self) {
+        self.schedule_reload(true);
+    }
+
+    /// rebuild only the widgets whose config changed, for config file changes.
+    pub fn reload_config(&mut self) {
+        self.schedule_reload(false);
+    }
+
+    fn schedule_reload(&mut self, full: bool) {
         log::info!("Reloading widgets...");
         self.first_time_initialized = true;
+        // a pending full reload stays full
+        self.full_reload_pending |= full;
 
-        let idle = self.event_loop_handle.insert_idle(App::reload_widgets);
+        let idle = self.event_loop_handle.insert_idle(|app| {
+            let full = mem::take(&mut app.full_reload_pending);
+            // errors are logged already
+            let _ = app.reload_widgets(full);
+        });
         if let Some(old) = self.reload_guard.replace(idle) {
             old.cancel()
         }
     }
 }
 
+// namespaces whose widgets differ between the two configs.
+fn changed_namespaces(old: &[WidgetConf], new: &[WidgetConf]) -> HashSet<String> {
+    fn group(confs: &[WidgetConf]) -> HashMap<&str, Vec<&WidgetConf>> {
+        let mut map: HashMap<&str, Vec<&WidgetConf>> = HashMap::new();
+        for conf in confs {
+            map.entry(conf.common().namespace.as_str())
+                .or_default()
+                .push(conf);
+        }
+        map
+    }
+    let old = group(old);
+    let new = group(new);
+    let same = |a: &[&WidgetConf], b: &[&WidgetConf]| {
+        a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.same_as(b))
+    };
+
+    old.keys()
+        .chain(new.keys())
+        .filter(|ns| match (old.get(*ns), new.get(*ns)) {
+            (Some(a), Some(b)) => !same(a, b),
+            _ => true,
+        })
+        .map(|ns| ns.to_string())
+        .collect()
+}
+
+fn notify_error(msg: &str) {
+    let msg = msg.to_string();
+    std::thread::spawn(move || {
+        let res = std::process::Command::new("notify-send")
+            .args([
+                "-a",
+                "way-edges",
+                "-u",
+                "critical",
+                "way-edges: invalid config",
+            ])
+            .arg(&msg)
+            .status();
+        if let Err(e) = res {
+            log::warn!("Failed to run notify-send: {e}");
+        }
+    });
+}
+
+// output focused by the compositor if any config asks for it,
+// asked once per build since it's a round trip to the compositor.
+fn focused_output<'a>(confs: impl IntoIterator<Item = &'a WidgetConf>) -> Option<String> {
+    confs
+        .into_iter()
+        .any(|c| c.common().monitor.uses_focused())
+        .then(backend::workspace::focused_output)
+        .flatten()
+}
+
 // the states from `App` that are needed when building widgets
 pub struct WidgetBuildingStates<'a> {
     pub event_loop_handle: &'a LoopHandle<'static, App>,
@@ -141,58 +559,83 @@ pub struct WidgetBuildingStates<'a> {
 #[derive(Debug, Default)]
 pub struct WidgetMap(HashMap<String, Vec<Arc<Mutex<Widget>>>>);
 impl WidgetMap {
-    fn new(widgets_config: Vec<WidgetConf>, app: &App) -> Result<Self, String> {
+    fn new<'a>(
+        widgets_config: impl IntoIterator<Item = &'a WidgetConf>,
+        app: &App,
+    ) -> Result<Self, String> {
         let mut map: HashMap<String, Vec<Arc<Mutex<Widget>>>> = HashMap::new();
-
-        for conf in widgets_config.iter() {
-            let common_config = conf.common();
-            let name = common_config.namespace.clone();
-            let confs: Vec<WlOutput> = match common_config.monitor.clone() {
-                MonitorSpecifier::Lists { ids, names } => {
-                    let mut ids = ids.clone();
-                    // find names and record index and put into ids
-                    for (id, output) in app.output_state.outputs().enumerate() {
-                        if let Some(info) = app.output_state.info(&output) {
-                            if let Some(output_name) = info.name {
-                                if names.contains(&output_name) {
-                                    ids.insert(id);
-                                }
-                            }
-                        }
-                    }
-
-                    app.output_state
-                        .outputs()
-                        .enumerate()
-                        .filter_map(|(id, output)| {
-                            if ids.contains(&id) {
-                                Some(output)
-                            } else {
-                                None
-                            }
-                        })
-                        .collect()
-                }
-                MonitorSpecifier::All => app.output_state.outputs().collect(),
-            };
-
-            for output in confs.into_iter() {
-                let ctx = Widget::init_widget(conf.clone(), output, app)?;
-                // TODO: CLONE
-                map.entry(name.clone()).or_default().push(ctx.clone());
+        let env = app.when_environment();
+        let widgets_config: Vec<_> = widgets_config.into_iter().collect();
+        let focused = focused_output(widgets_config.iter().copied());
+
+        for conf in widgets_config {
+            let name = &conf.common().namespace;
+            if app.disabled.contains(name) {
+                log::info!("skip disabled widget: {name}");
+                continue;
             }
+            if !conf.common().when.is_met(&env) {
+                log::info!("skip widget whose `when` is not met: {name}");
+                continue;
+            }
+            let ws = Self::init_widgets(conf, focused.as_deref(), app)?;
+            map.entry(name.clone()).or_default().extend(ws);
         }
 
         Ok(Self(map))
     }
 
-    fn get_widgets(&self, name: &str) -> Vec<Arc<Mutex<Widget>>> {
-        self.0.get(name).cloned().unwrap_or_default()
+    // one widget for each matched output
+    fn init_widgets(
+        conf: &WidgetConf,
+        focused: Option<&str>,
+        app: &App,
+    ) -> Result<Vec<Arc<Mutex<Widget>>>, String> {
+        let common_config = conf.common();
+        let confs: Vec<WlOutput> = app
+            .output_state
+            .outputs()
+            .enumerate()
+            .filter(|(index, output)| {
+                let info = app.output_state.info(output);
+                let name = info.as_ref().and_then(|i| i.name.as_deref());
+                let desc = OutputDesc {
+                    index: *index,
+                    name,
+                    make: info.as_ref().map(|i| i.make.as_str()),
+                    model: info.as_ref().map(|i| i.model.as_str()),
+                    description: info.as_ref().and_then(|i| i.description.as_deref()),
+                    // the first output if the compositor can not tell
+                    focused: match focused {
+                        Some(f) => name == Some(f),
+                        None => *index == 0,
+                    },
+                };
+                common_config.monitor.matches(&desc)
+            })
+            .map(|(_, output)| output)
+            .collect();
+
+        confs
+            .into_iter()
+            .map(|output| Widget::init_widget(conf.clone(), output, app))
+            .collect()
+    }
+
+    fn get_widgets(&self, pattern: &NamespacePattern) -> Vec<Arc<Mutex<Widget>>> {
+        self.0
+            .iter()
+            .filter(|(ns, _)| pattern.matches(ns))
+            .flat_map(|(_, ws)| ws.iter().cloned())
+            .collect()
     }
 }
 
 #[derive(Debug)]
 pub struct Widget {
+    pub namespace: String,
+    pub widget_type: &'static str,
+    pub edge: Anchor,
     pub monitor: MonitorSpecifier,
     pub configured: bool,
 
@@ -365,6 +808,34 @@ impl Widget {
             .toggle_pin(self.mouse_state.is_hovering());
         self.try_redraw(app);
     }
+    fn status(&self, output_state: &OutputState) -> WidgetStatus {
+        let edge = [
+            (Anchor::TOP, "top"),
+            (Anchor::LEFT, "left"),
+            (Anchor::BOTTOM, "bottom"),
+            (Anchor::RIGHT, "right"),
+        ]
+        .into_iter()
+        .find_map(|(a, name)| (a == self.edge).then_some(name))
+        .unwrap_or("unknown");
+        WidgetStatus {
+            namespace: self.namespace.clone(),
+            widget_type: self.widget_type.to_string(),
+            output: output_state.info(&self.output).and_then(|info| info.name),
+            edge: edge.to_string(),
+            pinned: self.window_pop_state.pin_state,
+            pop_progress: self.pop_animation.borrow().progress(),
+            configured: self.configured,
+        }
+    }
+    fn show(&mut self, app: &mut App) {
+        self.window_pop_state.enter();
+        self.try_redraw(app);
+    }
+    fn hide(&mut self, app: &mut App) {
+        self.window_pop_state.leave();
+        self.try_redraw(app);
+    }
     pub fn update_normal(&mut self, normal: u32, app: &mut App) {
         // IGNORING NORMAL SCALE IF FRACTIONAL SCALE IS AVAILABLE
         if self.scale.is_fractional() {
@@ -387,7 +858,7 @@ impl Widget {
             .set_margin(margins[0], margins[1], margins[2], margins[3]);
     }
     pub fn on_mouse_event(&mut self, app: &mut App, event: &PointerEvent) {
-        let Some(mut event) = self.mouse_state.from_wl_pointer(event) else {
+        let Some(mut event) = self.mouse_state.from_wl_pointer(event, app.modifiers) else {
             return;
         };
 
@@ -416,7 +887,7 @@ impl Widget {
             MouseEvent::Release(_, key) => {
                 if self
                     .window_pop_state
-                    .toggle_pin_with_key(key, data.hovering)
+                    .toggle_pin_with_key(key.code, data.hovering)
                 {
                     do_redraw()
                 }
@@ -463,10 +934,12 @@ impl Widget {
 
                 log::info!("initialized {}", $name);
 
-                builder.build(w)
+                builder.build(widget_type, w)
             }};
         }
 
+        let widget_type = conf.type_name();
+        let uses_modifiers = conf.event_maps().iter().any(|m| m.uses_modifiers());
         let s: Widget = match conf {
             config::def::WidgetConf::Btn(c) => {
                 ws!(c, config::def::Btn, "button", |b, w, _, s| {
@@ -491,6 +964,13 @@ impl Widget {
             }
         };
 
+        // modifiers are only sent to the surface with keyboard focus,
+        // on demand the compositor gives it on click
+        if uses_modifiers {
+            s.layer
+                .set_keyboard_interactivity(KeyboardInteractivity::OnDemand);
+        }
+
         Ok(Arc::new_cyclic(|weak| {
             SurfaceData::from_wl(s.layer.wl_surface()).store_widget(weak.clone());
             Mutex::new(s)
@@ -896,6 +1376,8 @@ impl<'a> WidgetBuilder<'a> {
         .make_rc();
         let animation_list = AnimationList::new();
         let mut window_pop_state = WindowPopState::new(
+            common.namespace.clone(),
+            app.output_state.info(&output).and_then(|info| info.name),
             pop_animation,
             common.pinnable,
             common.pin_with_key,
@@ -925,7 +1407,7 @@ impl<'a> WidgetBuilder<'a> {
             common_config: common,
         })
     }
-    pub fn build(self, w: Box<dyn WidgetContext>) -> Widget {
+    pub fn build(self, widget_type: &'static str, w: Box<dyn WidgetContext>) -> Widget {
         let Self {
             monitor,
             output,
@@ -946,6 +1428,9 @@ impl<'a> WidgetBuilder<'a> {
         let draw_core = DrawCore::new(&common_config);
 
         Widget {
+            namespace: common_config.namespace.clone(),
+            widget_type,
+            edge: common_config.edge,
             monitor,
             configured: false,
             output,
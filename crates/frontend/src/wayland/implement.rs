@@ -1,7 +1,7 @@
 use smithay_client_toolkit::{
     compositor::{CompositorHandler, SurfaceData as SctkSurfaceData},
-    delegate_compositor, delegate_layer, delegate_output, delegate_pointer, delegate_registry,
-    delegate_seat, delegate_shm, delegate_simple,
+    delegate_compositor, delegate_keyboard, delegate_layer, delegate_output, delegate_pointer,
+    delegate_registry, delegate_seat, delegate_shm, delegate_simple,
     output::{OutputHandler, OutputState},
     reexports::protocols::wp::{
         fractional_scale::v1::client::{
@@ -13,6 +13,7 @@ use smithay_client_toolkit::{
     registry::{ProvidesRegistryState, RegistryState},
     registry_handlers,
     seat::{
+        keyboard::{KeyEvent, KeyboardHandler, Keysym, Modifiers, RawModifiers},
         pointer::{PointerEvent, PointerHandler},
         Capability, SeatHandler, SeatState,
     },
@@ -25,7 +26,7 @@ use smithay_client_toolkit::{
 use wayland_client::{
     delegate_noop,
     protocol::{
-        wl_output, wl_pointer, wl_seat,
+        wl_keyboard, wl_output, wl_pointer, wl_seat,
         wl_surface::{self, WlSurface},
     },
     Connection, QueueHandle,
@@ -189,6 +190,15 @@ impl SeatHandler for App {
                 .expect("Failed to create pointer");
             self.pointer = Some(pointer);
         }
+        // only for the modifiers held while clicking
+        if capability == Capability::Keyboard && self.keyboard.is_none() {
+            log::info!("got keyboard capability");
+
+            match self.seat_state.get_keyboard(qh, &seat, None) {
+                Ok(keyboard) => self.keyboard = Some(keyboard),
+                Err(e) => log::error!("Failed to create keyboard: {e}"),
+            }
+        }
     }
 
     fn remove_capability(
@@ -203,6 +213,11 @@ impl SeatHandler for App {
             log::warn!("remove pointer capability");
             self.pointer.take().unwrap().release();
         }
+        if capability == Capability::Keyboard && self.keyboard.is_some() {
+            log::warn!("remove keyboard capability");
+            self.keyboard.take().unwrap().release();
+            self.modifiers = Default::default();
+        }
     }
 
     fn remove_seat(&mut self, _: &Connection, _: &QueueHandle<Self>, _: wl_seat::WlSeat) {}
@@ -244,6 +259,69 @@ impl PointerHandler for App {
     }
 }
 
+impl KeyboardHandler for App {
+    fn enter(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        _: &wl_keyboard::WlKeyboard,
+        _: &WlSurface,
+        _: u32,
+        _: &[u32],
+        _: &[Keysym],
+    ) {
+    }
+
+    fn leave(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        _: &wl_keyboard::WlKeyboard,
+        _: &WlSurface,
+        _: u32,
+    ) {
+        self.modifiers = Default::default();
+    }
+
+    fn press_key(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        _: &wl_keyboard::WlKeyboard,
+        _: u32,
+        _: KeyEvent,
+    ) {
+    }
+
+    fn release_key(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        _: &wl_keyboard::WlKeyboard,
+        _: u32,
+        _: KeyEvent,
+    ) {
+    }
+
+    fn update_modifiers(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        _: &wl_keyboard::WlKeyboard,
+        _: u32,
+        modifiers: Modifiers,
+        _: RawModifiers,
+        _: u32,
+    ) {
+        self.modifiers = config::def::shared::Modifiers {
+            shift: modifiers.shift,
+            ctrl: modifiers.ctrl,
+            alt: modifiers.alt,
+            logo: modifiers.logo,
+        };
+    }
+}
+
 impl wayland_client::Dispatch<WpFractionalScaleV1, WlSurface> for App {
     fn event(
         app: &mut App,
@@ -286,3 +364,4 @@ delegate_noop!(App: WpViewport);
 
 delegate_seat!(App);
 delegate_pointer!(App);
+delegate_keyboard!(App);
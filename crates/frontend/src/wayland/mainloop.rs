@@ -1,4 +1,4 @@
-use std::time::Duration;
+use std::{collections::HashSet, time::Duration};
 
 use backend::{
     config_file_watch::start_configuration_file_watcher, ipc::start_ipc,
@@ -20,7 +20,7 @@ use crate::wayland::app::WidgetMap;
 
 use super::app::App;
 
-pub fn run_app(show_mouse_key: bool) {
+pub fn run_app(show_mouse_key: bool, notify_errors: bool) {
     let conn = Connection::connect_to_env().unwrap();
 
     let (globals, event_queue) = registry_queue_init(&conn).unwrap();
@@ -47,10 +47,12 @@ pub fn run_app(show_mouse_key: bool) {
 
     let mut app = App {
         reload_guard: None,
+        full_reload_pending: false,
         first_time_initialized: false,
 
         exit: false,
         show_mouse_key,
+        notify_errors,
         queue_handle: qh,
         event_loop_handle: event_loop.handle(),
         signal,
@@ -64,9 +66,13 @@ pub fn run_app(show_mouse_key: bool) {
         shm,
         pool,
         pointer: None,
+        keyboard: None,
+        modifiers: Default::default(),
         shell: layer_shell,
 
         widget_map: WidgetMap::default(),
+        widget_confs: Vec::new(),
+        disabled: HashSet::new(),
     };
 
     init_backend_runtime_handle();
@@ -76,12 +82,12 @@ pub fn run_app(show_mouse_key: bool) {
     event_loop
         .handle()
         .insert_source(r, |event, _, app| {
-            let calloop::channel::Event::Msg(cmd) = event else {
+            let calloop::channel::Event::Msg((cmd, responder)) = event else {
                 log::error!("IPC server shutdown, exiting...");
                 app.exit = true;
                 return;
             };
-            app.handle_ipc(cmd);
+            responder.reply(app.handle_ipc(cmd));
         })
         .unwrap();
 
@@ -95,7 +101,21 @@ pub fn run_app(show_mouse_key: bool) {
                 app.exit = true;
                 return;
             };
-            app.reload();
+            app.reload_config();
+        })
+        .unwrap();
+
+    let (sender, r) = calloop::channel::channel();
+    config::def::action::set_action_handler(move |action| {
+        // the receiver lives as long as the event loop
+        let _ = sender.send(action);
+    });
+    event_loop
+        .handle()
+        .insert_source(r, |event, _, app| {
+            if let calloop::channel::Event::Msg(action) = event {
+                app.run_action(action);
+            }
         })
         .unwrap();
 
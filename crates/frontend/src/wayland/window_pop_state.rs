@@ -1,9 +1,14 @@
 use std::rc::Rc;
 
+use backend::ipc::{emit_event, IPCEvent, PopDirection};
+
 use crate::animation::{ToggleAnimationRc, ToggleDirection};
 
 #[derive(Debug)]
 pub struct WindowPopState {
+    namespace: String,
+    // name of the output, for events
+    output: Option<String>,
     pinnale: bool,
     pin_with_key: bool,
     pin_key: u32,
@@ -12,8 +17,17 @@ pub struct WindowPopState {
     pub pop_animation: ToggleAnimationRc,
 }
 impl WindowPopState {
-    pub fn new(ani: ToggleAnimationRc, pinnale: bool, pin_with_key: bool, pin_key: u32) -> Self {
+    pub fn new(
+        namespace: String,
+        output: Option<String>,
+        ani: ToggleAnimationRc,
+        pinnale: bool,
+        pin_with_key: bool,
+        pin_key: u32,
+    ) -> Self {
         Self {
+            namespace,
+            output,
             pin_state: false,
             pop_state: None,
             pop_animation: ani,
@@ -33,10 +47,15 @@ impl WindowPopState {
         self.invalidate_pop();
         let state = !self.pin_state;
         self.pin_state = state;
+        emit_event(IPCEvent::Pin {
+            namespace: self.namespace.clone(),
+            output: self.output.clone(),
+            pinned: state,
+        });
         if is_hovering {
             return;
         }
-        self.pop_animation.borrow_mut().set_direction(state.into());
+        self.set_direction(state.into());
     }
     pub fn toggle_pin_with_key(&mut self, key: u32, is_hovering: bool) -> bool {
         if !self.pin_with_key || key != self.pin_key {
@@ -50,17 +69,32 @@ impl WindowPopState {
         if self.pin_state {
             return;
         }
-        self.pop_animation
-            .borrow_mut()
-            .set_direction(ToggleDirection::Forward);
+        self.set_direction(ToggleDirection::Forward);
     }
     pub fn leave(&mut self) {
         self.invalidate_pop();
         if self.pin_state {
             return;
         }
-        self.pop_animation
-            .borrow_mut()
-            .set_direction(ToggleDirection::Backward);
+        self.set_direction(ToggleDirection::Backward);
+    }
+    fn set_direction(&mut self, direction: ToggleDirection) {
+        {
+            let mut ani = self.pop_animation.borrow_mut();
+            if ani.direction == direction {
+                return;
+            }
+            ani.set_direction(direction);
+        }
+
+        let direction = match direction {
+            ToggleDirection::Forward => PopDirection::Forward,
+            ToggleDirection::Backward => PopDirection::Backward,
+        };
+        emit_event(IPCEvent::Pop {
+            namespace: self.namespace.clone(),
+            output: self.output.clone(),
+            direction,
+        });
     }
 }
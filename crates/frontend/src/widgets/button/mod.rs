@@ -4,7 +4,10 @@ use crate::{
     mouse_state::{MouseEvent, MouseStateData},
     wayland::app::WidgetBuilder,
 };
-use config::def::{shared::KeyEventMap, widgets::button::BtnConfig};
+use config::def::{
+    shared::{KeyBinding, KeyEventMap},
+    widgets::button::BtnConfig,
+};
 use draw::DrawConfig;
 
 use super::WidgetContext;
@@ -36,9 +39,7 @@ impl WidgetContext for BtnContext {
     }
 
     fn on_mouse_event(&mut self, data: &MouseStateData, event: MouseEvent) -> bool {
-        if let MouseEvent::Release(_, k) = event {
-            self.event_map.call(k);
-        }
+        event.call_event_map(&mut self.event_map);
 
         let new_pressing_state = data.pressing.is_some();
         if new_pressing_state != self.pressing {
@@ -48,4 +49,11 @@ impl WidgetContext for BtnContext {
             false
         }
     }
+
+    fn on_ipc_trigger(&mut self, item_id: Option<&str>, key: KeyBinding) -> Result<(), String> {
+        if item_id.is_some() {
+            return Err("button has no items".to_string());
+        }
+        self.event_map.trigger(key)
+    }
 }
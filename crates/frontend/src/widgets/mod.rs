@@ -1,6 +1,7 @@
 use cairo::ImageSurface;
 
 use crate::mouse_state::{MouseEvent, MouseStateData};
+use config::def::shared::KeyBinding;
 
 pub mod button;
 pub mod slide;
@@ -10,4 +11,33 @@ pub mod wrapbox;
 pub trait WidgetContext: std::fmt::Debug {
     fn redraw(&mut self) -> ImageSurface;
     fn on_mouse_event(&mut self, data: &MouseStateData, event: MouseEvent) -> bool;
+
+    /// whether `on_ipc_set` takes values, checked for every matched widget before setting any.
+    fn accepts_ipc_set(&self) -> bool {
+        false
+    }
+
+    /// value pushed by `way-edges set`, returns the pop duration if the widget should pop.
+    fn on_ipc_set(&mut self, _value: f64) -> Result<Option<u64>, String> {
+        Err("this widget does not accept values".to_string())
+    }
+
+    /// content pushed by `way-edges push` to the item with `item_id` inside this widget.
+    fn on_ipc_push(
+        &mut self,
+        _item_id: &str,
+        _value: &str,
+        _label: Option<&str>,
+    ) -> Result<(), String> {
+        Err("this widget has no items".to_string())
+    }
+
+    /// run the event map action bound to `key` by `way-edges trigger`,
+    /// `item_id` is the wrap-box item, if any.
+    fn on_ipc_trigger(&mut self, item_id: Option<&str>, _key: KeyBinding) -> Result<(), String> {
+        match item_id {
+            Some(_) => Err("this widget has no items".to_string()),
+            None => Err("this widget has no event map".to_string()),
+        }
+    }
 }
@@ -8,14 +8,16 @@ use super::base::{
 use crate::{
     mouse_state::{MouseEvent, MouseStateData},
     wayland::app::WidgetBuilder,
-    widgets::{slide::base::event::ProgressDataf, WidgetContext},
+    widgets::{
+        slide::base::event::{emit_slider_value, ProgressDataf},
+        WidgetContext,
+    },
 };
 
 use config::def::widgets::slide::{base::SlideConfig, preset::BacklightConfig};
 
 #[derive(Debug)]
 pub struct BacklightContext {
-    #[allow(dead_code)]
     backend_id: i32,
     device: Option<String>,
 
@@ -24,6 +26,11 @@ pub struct BacklightContext {
     progress_state: ProgressState<ProgressDataf>,
     only_redraw_on_internal_update: bool,
 }
+impl Drop for BacklightContext {
+    fn drop(&mut self) {
+        backend::backlight::unregister_callback(self.backend_id);
+    }
+}
 impl WidgetContext for BacklightContext {
     fn redraw(&mut self) -> ImageSurface {
         let p = self.progress_state.p();
@@ -52,11 +59,13 @@ pub fn preset(
     let progress = Rc::new(Cell::new(0.));
 
     let progress_weak = Rc::downgrade(&progress);
+    let namespace = builder.common_config.namespace.clone();
     let redraw_signal = builder.make_redraw_channel(move |_, p| {
         let Some(progress) = progress_weak.upgrade() else {
             return;
         };
         progress.set(p);
+        emit_slider_value(&namespace, p);
     });
     let backend_id = backend::backlight::register_callback(redraw_signal, device.clone()).unwrap();
 
@@ -65,7 +74,12 @@ pub fn preset(
         backend_id,
         device,
         draw_conf: DrawConfig::new(edge, &w_conf),
-        progress_state: setup_event(edge, &w_conf, progress),
+        progress_state: setup_event(
+            builder.common_config.namespace.clone(),
+            edge,
+            &w_conf,
+            progress,
+        ),
         only_redraw_on_internal_update: w_conf.redraw_only_on_internal_update,
     }
 }
@@ -1,7 +1,9 @@
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
 use std::rc::Rc;
 
 use crate::mouse_state::MouseEvent;
+use backend::ipc::{emit_event, IPCEvent};
 use config::def::widgets::slide::base::SlideConfig;
 use smithay_client_toolkit::seat::pointer::BTN_LEFT;
 use smithay_client_toolkit::shell::wlr_layer::Anchor;
@@ -38,12 +40,30 @@ fn make_translate_func(edge: Anchor) -> fn(i32, i32, (f64, f64)) -> f64 {
     }
 }
 
+thread_local! {
+    // last value sent per namespace, every output has its own widget of the same slider
+    static LAST_EMITTED: RefCell<HashMap<String, f64>> = RefCell::new(HashMap::new());
+}
+
+/// send a changed slider value to ipc subscribers, once for all outputs.
+pub fn emit_slider_value(namespace: &str, value: f64) {
+    let changed =
+        LAST_EMITTED.with_borrow_mut(|m| m.insert(namespace.to_string(), value) != Some(value));
+    if changed {
+        emit_event(IPCEvent::SliderValue {
+            namespace: namespace.to_string(),
+            value,
+        });
+    }
+}
+
 pub trait ProgressData {
     fn get(&self) -> f64;
     fn set(&mut self, value: f64);
 }
 
 pub fn setup_event<T: ProgressData>(
+    namespace: String,
     edge: Anchor,
     w_conf: &SlideConfig,
     data: T,
@@ -52,6 +72,7 @@ pub fn setup_event<T: ProgressData>(
     let left_pressing = false;
 
     ProgressState {
+        namespace,
         left_pressing,
         scroll_unit: w_conf.scroll_unit,
         length: w_conf.size().unwrap().1 as i32 - 2 * w_conf.border_width,
@@ -74,6 +95,7 @@ impl ProgressData for ProgressDataf {
 
 #[derive(Debug)]
 pub struct ProgressState<T: ProgressData> {
+    namespace: String,
     left_pressing: bool,
     length: i32,
     border_width: i32,
@@ -89,6 +111,11 @@ impl<T: ProgressData> ProgressState<T> {
     pub fn p(&self) -> f64 {
         self.progress.get()
     }
+    /// set from outside of the mouse events, like ipc.
+    pub fn set(&mut self, value: f64) {
+        self.progress.set(value);
+        emit_slider_value(&self.namespace, value);
+    }
     pub fn data(&mut self) -> &mut T {
         &mut self.progress
     }
@@ -106,7 +133,7 @@ impl<T: ProgressData> ProgressState<T> {
                 }
             }
             MouseEvent::Release(pos, key) => {
-                if key == BTN_LEFT {
+                if key.code == BTN_LEFT {
                     self.left_pressing = false;
                     p = Some(self.calculate(pos));
                 }
@@ -122,9 +149,11 @@ impl<T: ProgressData> ProgressState<T> {
             _ => {}
         }
 
-        #[allow(clippy::unnecessary_unwrap)]
-        if update_progress_immediate && p.is_some() {
-            self.progress.set(p.unwrap());
+        if let Some(p) = p {
+            if update_progress_immediate {
+                self.progress.set(p);
+            }
+            emit_slider_value(&self.namespace, p);
         }
 
         p
@@ -3,8 +3,11 @@ use interval_task::runner::Runner;
 use std::{cell::Cell, rc::Rc, time::Duration};
 
 use config::def::{
-    shared::KeyEventMap,
-    widgets::slide::{base::SlideConfig, preset::CustomConfig},
+    shared::{KeyBinding, KeyEventMap},
+    widgets::slide::{
+        base::SlideConfig,
+        preset::{CustomConfig, IpcConfig},
+    },
 };
 use util::{
     shell::{shell_cmd, shell_cmd_non_block},
@@ -19,7 +22,7 @@ use crate::{
     mouse_state::{MouseEvent, MouseStateData},
     wayland::app::WidgetBuilder,
     widgets::{
-        slide::base::event::{ProgressData, ProgressDataf},
+        slide::base::event::{emit_slider_value, ProgressData, ProgressDataf},
         WidgetContext,
     },
 };
@@ -30,6 +33,8 @@ pub struct CustomContext {
     runner: Option<Runner<()>>,
     event_map: KeyEventMap,
     on_change: Option<Template>,
+    // `Some` for ipc preset, contains pop duration if pop on set
+    ipc: Option<Option<u64>>,
 
     draw_conf: DrawConfig,
 
@@ -43,9 +48,7 @@ impl WidgetContext for CustomContext {
     }
 
     fn on_mouse_event(&mut self, _: &MouseStateData, event: MouseEvent) -> bool {
-        if let MouseEvent::Release(_, key) = event.clone() {
-            self.event_map.call(key);
-        }
+        event.call_event_map(&mut self.event_map);
 
         if let Some(p) = self
             .progress_state
@@ -57,6 +60,29 @@ impl WidgetContext for CustomContext {
             false
         }
     }
+
+    fn accepts_ipc_set(&self) -> bool {
+        self.ipc.is_some()
+    }
+
+    fn on_ipc_set(&mut self, value: f64) -> Result<Option<u64>, String> {
+        let Some(pop) = self.ipc else {
+            return Err("only slider with `ipc` preset accepts values".to_string());
+        };
+        if !value.is_finite() {
+            return Err(format!("Invalid value: {value}"));
+        }
+        // `on-change-command` is not run here, the value comes from its owner
+        self.progress_state.set(value.clamp(0., 1.));
+        Ok(pop)
+    }
+
+    fn on_ipc_trigger(&mut self, item_id: Option<&str>, key: KeyBinding) -> Result<(), String> {
+        if item_id.is_some() {
+            return Err("slider has no items".to_string());
+        }
+        self.event_map.trigger(key)
+    }
 }
 
 impl CustomContext {
@@ -101,8 +127,41 @@ pub fn custom_preset(
         runner,
         event_map,
         on_change,
+        ipc: None,
+        draw_conf: DrawConfig::new(edge, &w_conf),
+        progress_state: setup_event(
+            builder.common_config.namespace.clone(),
+            edge,
+            &w_conf,
+            progress_data,
+        ),
+        only_redraw_on_internal_update: w_conf.redraw_only_on_internal_update,
+    }
+}
+
+pub fn ipc_preset(
+    builder: &mut WidgetBuilder,
+    w_conf: SlideConfig,
+    mut preset_conf: IpcConfig,
+) -> impl WidgetContext {
+    let progress_data = Rc::new(Cell::new(preset_conf.initial_value.clamp(0., 1.)));
+    let event_map = std::mem::take(&mut preset_conf.event_map);
+    let on_change = preset_conf.on_change_command.take();
+    let pop = preset_conf.pop_on_set.then_some(preset_conf.pop_duration);
+
+    let edge = builder.common_config.edge;
+    CustomContext {
+        runner: None,
+        event_map,
+        on_change,
+        ipc: Some(pop),
         draw_conf: DrawConfig::new(edge, &w_conf),
-        progress_state: setup_event(edge, &w_conf, progress_data),
+        progress_state: setup_event(
+            builder.common_config.namespace.clone(),
+            edge,
+            &w_conf,
+            progress_data,
+        ),
         only_redraw_on_internal_update: w_conf.redraw_only_on_internal_update,
     }
 }
@@ -117,11 +176,13 @@ fn interval_update(
     }
 
     let progress_cache_weak = Rc::downgrade(progress_cache);
+    let namespace = window.common_config.namespace.clone();
     let redraw_signal = window.make_redraw_channel(move |_, p| {
         let Some(mut progress_cache) = progress_cache_weak.upgrade() else {
             return;
         };
         progress_cache.set(p);
+        emit_slider_value(&namespace, p);
     });
 
     let cmd = preset_conf.update_command.clone();
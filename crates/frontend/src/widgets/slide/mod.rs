@@ -32,5 +32,6 @@ pub fn init_widget(
         Preset::Custom(custom_config) => {
             Box::new(custom::custom_preset(builder, w_conf, custom_config))
         }
+        Preset::Ipc(ipc_config) => Box::new(custom::ipc_preset(builder, w_conf, ipc_config)),
     }
 }
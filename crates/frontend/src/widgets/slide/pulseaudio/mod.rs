@@ -9,7 +9,7 @@ use super::base::{
     draw::DrawConfig,
     event::{setup_event, ProgressState},
 };
-use crate::widgets::slide::base::event::ProgressData;
+use crate::widgets::slide::base::event::{emit_slider_value, ProgressData};
 use crate::{
     animation::ToggleAnimationRc,
     mouse_state::{MouseEvent, MouseStateData},
@@ -49,7 +49,6 @@ impl Progress {
 
 #[derive(Debug)]
 pub struct PulseAudioContext {
-    #[allow(dead_code)]
     backend_id: i32,
     device: PulseAudioDevice,
     debounce_ctx: Option<Arc<()>>,
@@ -64,6 +63,11 @@ pub struct PulseAudioContext {
     progress_state: ProgressState<Progress>,
     only_redraw_on_internal_update: bool,
 }
+impl Drop for PulseAudioContext {
+    fn drop(&mut self) {
+        backend::pulseaudio::unregister_callback(self.backend_id);
+    }
+}
 impl WidgetContext for PulseAudioContext {
     fn redraw(&mut self) -> ImageSurface {
         let mute_y = self.mute_animation.borrow_mut().progress();
@@ -81,7 +85,7 @@ impl WidgetContext for PulseAudioContext {
     }
 
     fn on_mouse_event(&mut self, _: &MouseStateData, event: MouseEvent) -> bool {
-        if let MouseEvent::Release(_, BTN_RIGHT) = event {
+        if matches!(event, MouseEvent::Release(_, key) if key.code == BTN_RIGHT) {
             set_mute(self.device.clone(), !self.progress_state.data().is_muted());
         }
 
@@ -116,6 +120,7 @@ fn common(
 
     let vinfo_weak = Rc::downgrade(&vinfo);
     let mute_animation_weak = mute_animation.downgrade();
+    let namespace = builder.common_config.namespace.clone();
     let redraw_signal = builder.make_redraw_channel(move |_, vinfo: VInfo| {
         let Some(vinfo_old) = vinfo_weak.upgrade() else {
             return;
@@ -130,6 +135,7 @@ fn common(
                 .set_direction(vinfo.is_muted.into());
         }
         vinfo_old.set(vinfo);
+        emit_slider_value(&namespace, vinfo.vol);
     });
     let backend_id = backend::pulseaudio::register_callback(redraw_signal, device.clone()).unwrap();
 
@@ -143,7 +149,12 @@ fn common(
         mute_text_color,
         mute_animation,
         draw_conf: DrawConfig::new(edge, &w_conf),
-        progress_state: setup_event(edge, &w_conf, vinfo.into()),
+        progress_state: setup_event(
+            builder.common_config.namespace.clone(),
+            edge,
+            &w_conf,
+            vinfo.into(),
+        ),
         only_redraw_on_internal_update: w_conf.redraw_only_on_internal_update,
         debounce_ctx: None,
     }
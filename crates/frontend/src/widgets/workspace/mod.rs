@@ -113,7 +113,7 @@ impl WidgetContext for WorkspaceCtx {
         }
         match event {
             MouseEvent::Release(pos, key) => {
-                if key == BTN_LEFT {
+                if key.code == BTN_LEFT {
                     should_redraw = hhh!(self.hover_data, pos);
                     let id = self.hover_data.hover_id;
                     if id >= 0 {
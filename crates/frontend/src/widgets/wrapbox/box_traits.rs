@@ -4,6 +4,7 @@ use cairo::ImageSurface;
 use way_edges_derive::wrap_rc;
 
 use crate::{animation::AnimationList, buffer::Buffer, mouse_state::MouseEvent};
+use config::def::shared::KeyBinding;
 
 use super::grid::GridBox;
 
@@ -14,6 +15,12 @@ pub trait BoxedWidget: Debug {
     fn on_mouse_event(&mut self, _: MouseEvent) -> bool {
         false
     }
+    fn on_ipc_push(&mut self, _value: &str, _label: Option<&str>) -> Result<(), String> {
+        Err("only items with `ipc` preset accept pushed content".to_string())
+    }
+    fn on_ipc_trigger(&mut self, _key: KeyBinding) -> Result<(), String> {
+        Err("this item has no event map".to_string())
+    }
 }
 
 #[wrap_rc(rc = "pub", normal = "pub")]
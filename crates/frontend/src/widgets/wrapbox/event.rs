@@ -70,6 +70,9 @@ impl LastWidget {
     fn release_press(&mut self) {
         self.press_lock = false
     }
+    fn current(&self) -> Option<BoxedWidgetCtxRc> {
+        self.current_widget.as_ref().and_then(|w| w.upgrade())
+    }
     fn take_current(&mut self) -> Option<BoxedWidgetCtxRc> {
         self.current_widget.take().map(|w| w.upgrade().unwrap())
     }
@@ -132,6 +135,12 @@ pub fn on_mouse_event(event: MouseEvent, ctx: &mut BoxContext) -> bool {
                 }
             }
         }
+        // the hovered item keeps hovered, or it would get `Enter` again on the next motion
+        scroll @ MouseEvent::Scroll(..) => {
+            if let Some(current) = ctx.last_widget.current() {
+                redraw.or(current.borrow_mut().on_mouse_event(scroll));
+            }
+        }
         common_event => {
             if let Some(last) = ctx.last_widget.take_current() {
                 redraw.or(last.borrow_mut().on_mouse_event(common_event));
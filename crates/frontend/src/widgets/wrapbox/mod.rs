@@ -4,14 +4,17 @@ mod grid;
 mod outlook;
 mod widgets;
 
-use std::{cell::Cell, rc::Rc};
+use std::{cell::Cell, collections::HashMap, rc::Rc};
 
 use crate::{
     animation::{AnimationList, ToggleAnimationRc},
     wayland::app::{App, WidgetBuilder},
 };
 use box_traits::{BoxedWidgetCtx, BoxedWidgetCtxRc, BoxedWidgetGrid};
-use config::def::{shared::Curve, widgets::wrapbox::BoxConfig};
+use config::def::{
+    shared::{Curve, KeyBinding},
+    widgets::wrapbox::BoxConfig,
+};
 use event::LastWidget;
 use grid::{builder::GrideBoxBuilder, GridBox};
 use outlook::{init_outlook, OutlookDraw};
@@ -22,6 +25,7 @@ use super::WidgetContext;
 pub struct BoxContext {
     grid_box: GridBox<BoxedWidgetCtxRc>,
     outlook_draw_conf: Box<dyn OutlookDraw>,
+    item_ids: HashMap<String, BoxedWidgetCtxRc>,
 
     last_widget: LastWidget,
     leave_box_state: bool,
@@ -39,6 +43,28 @@ impl WidgetContext for BoxContext {
     ) -> bool {
         event::on_mouse_event(event, self)
     }
+
+    fn on_ipc_push(
+        &mut self,
+        item_id: &str,
+        value: &str,
+        label: Option<&str>,
+    ) -> Result<(), String> {
+        let item = self
+            .item_ids
+            .get(item_id)
+            .ok_or_else(|| format!("No item found with id: {item_id}"))?;
+        item.borrow_mut().ctx.on_ipc_push(value, label)
+    }
+
+    fn on_ipc_trigger(&mut self, item_id: Option<&str>, key: KeyBinding) -> Result<(), String> {
+        let item_id = item_id.ok_or("wrap-box has no event map, use <namespace>/<item-id>")?;
+        let item = self
+            .item_ids
+            .get(item_id)
+            .ok_or_else(|| format!("No item found with id: {item_id}"))?;
+        item.borrow_mut().ctx.on_ipc_trigger(key)
+    }
 }
 
 pub fn init_widget(builder: &mut WidgetBuilder, w_conf: BoxConfig) -> impl WidgetContext {
@@ -47,11 +73,12 @@ pub fn init_widget(builder: &mut WidgetBuilder, w_conf: BoxConfig) -> impl Widge
         builder.common_config.edge,
         builder.common_config.offset,
     );
-    let grid_box = init_boxed_widgets(builder, w_conf);
+    let (grid_box, item_ids) = init_boxed_widgets(builder, w_conf);
 
     BoxContext {
         grid_box,
         outlook_draw_conf,
+        item_ids,
         // last hover widget, for trigger mouse leave option for that widget.
         last_widget: LastWidget::new(),
         // because mouse leave event is before release,
@@ -60,8 +87,12 @@ pub fn init_widget(builder: &mut WidgetBuilder, w_conf: BoxConfig) -> impl Widge
     }
 }
 
-fn init_boxed_widgets(window: &mut WidgetBuilder, mut box_conf: BoxConfig) -> BoxedWidgetGrid {
+fn init_boxed_widgets(
+    window: &mut WidgetBuilder,
+    mut box_conf: BoxConfig,
+) -> (BoxedWidgetGrid, HashMap<String, BoxedWidgetCtxRc>) {
     let mut builder = GrideBoxBuilder::<BoxedWidgetCtxRc>::new();
+    let mut item_ids = HashMap::new();
     let ws = std::mem::take(&mut box_conf.items);
 
     use config::def::widgets::wrapbox::BoxedWidget;
@@ -96,10 +127,14 @@ fn init_boxed_widgets(window: &mut WidgetBuilder, mut box_conf: BoxConfig) -> Bo
             }
         };
 
-        builder.add(boxed_widget_context.make_rc(), (w.index[0], w.index[1]));
+        let boxed_widget_context = boxed_widget_context.make_rc();
+        if let Some(id) = w.id {
+            item_ids.insert(id, boxed_widget_context.clone());
+        }
+        builder.add(boxed_widget_context, (w.index[0], w.index[1]));
     });
 
-    builder.build(box_conf.gap, box_conf.align)
+    (builder.build(box_conf.gap, box_conf.align), item_ids)
 }
 
 struct BoxTemporaryCtx<'a, 'b> {
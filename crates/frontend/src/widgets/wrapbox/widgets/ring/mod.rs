@@ -5,11 +5,12 @@ use std::cell::UnsafeCell;
 use std::rc::Rc;
 
 use cairo::ImageSurface;
-use config::def::shared::KeyEventMap;
+use calloop::channel::Sender;
+use config::def::shared::{KeyBinding, KeyEventMap};
 use draw::RingDrawer;
 use interval_task::runner::Runner;
 
-use config::def::widgets::wrapbox::ring::RingConfig;
+use config::def::widgets::wrapbox::ring::{RingConfig, RingPreset};
 use preset::RunnerResult;
 
 use crate::mouse_state::MouseEvent;
@@ -19,7 +20,9 @@ use crate::widgets::wrapbox::BoxTemporaryCtx;
 #[derive(Debug)]
 pub struct RingCtx {
     #[allow(dead_code)]
-    runner: Runner<()>,
+    runner: Option<Runner<()>>,
+    // only for ipc preset
+    ipc_signal: Option<Sender<RunnerResult>>,
     current: Rc<UnsafeCell<RunnerResult>>,
     drawer: RingDrawer,
     event_map: KeyEventMap,
@@ -31,6 +34,7 @@ impl BoxedWidget for RingCtx {
         self.drawer.draw(current)
     }
     fn on_mouse_event(&mut self, event: MouseEvent) -> bool {
+        event.call_event_map(&mut self.event_map);
         match event {
             MouseEvent::Enter(_) => {
                 self.drawer
@@ -46,20 +50,38 @@ impl BoxedWidget for RingCtx {
                     .set_direction(crate::animation::ToggleDirection::Backward);
                 true
             }
-            MouseEvent::Release(_, key) => {
-                self.event_map.call(key);
-                false
-            }
             _ => false,
         }
     }
+    fn on_ipc_trigger(&mut self, key: KeyBinding) -> Result<(), String> {
+        self.event_map.trigger(key)
+    }
+    fn on_ipc_push(&mut self, value: &str, label: Option<&str>) -> Result<(), String> {
+        let Some(signal) = self.ipc_signal.as_ref() else {
+            return Err("only ring with `ipc` preset accepts pushed content".to_string());
+        };
+        let progress = value
+            .trim()
+            .parse::<f64>()
+            .map_err(|e| format!("Invalid progress `{value}`: {e}"))?;
+        signal
+            .send(RunnerResult {
+                progress: progress.clamp(0., 1.),
+                preset_text: label.unwrap_or_default().to_string(),
+            })
+            .map_err(|e| format!("Redraw signal error: {e}"))
+    }
 }
 
 pub fn init_widget(box_temp_ctx: &mut BoxTemporaryCtx, mut conf: RingConfig) -> impl BoxedWidget {
     let drawer = RingDrawer::new(box_temp_ctx, &mut conf);
 
     // runner
-    let current = Rc::new(UnsafeCell::new(RunnerResult::default()));
+    let mut initial = RunnerResult::default();
+    if let RingPreset::Ipc { initial_value } = &conf.preset {
+        initial.progress = initial_value.clamp(0., 1.);
+    }
+    let current = Rc::new(UnsafeCell::new(initial));
     let current_weak = Rc::downgrade(&current);
     let redraw_signal = box_temp_ctx.make_redraw_channel(move |_, msg| {
         let Some(current) = current_weak.upgrade() else {
@@ -67,11 +89,15 @@ pub fn init_widget(box_temp_ctx: &mut BoxTemporaryCtx, mut conf: RingConfig) ->
         };
         unsafe { *current.get().as_mut().unwrap() = msg };
     });
+    let ipc_signal = matches!(conf.preset, RingPreset::Ipc { .. }).then(|| redraw_signal.clone());
     let mut runner = preset::parse_preset(conf.preset, redraw_signal);
-    runner.start().unwrap();
+    if let Some(runner) = runner.as_mut() {
+        runner.start().unwrap();
+    }
 
     RingCtx {
         runner,
+        ipc_signal,
         current,
         drawer,
         event_map: conf.event_map,
@@ -173,8 +173,8 @@ pub struct RunnerResult {
     pub preset_text: String,
 }
 
-pub fn parse_preset(preset: RingPreset, s: Sender<RunnerResult>) -> Runner<()> {
-    match preset {
+pub fn parse_preset(preset: RingPreset, s: Sender<RunnerResult>) -> Option<Runner<()>> {
+    let runner = match preset {
         RingPreset::Ram { update_interval } => ram(s, update_interval),
         RingPreset::Swap { update_interval } => swap(s, update_interval),
         RingPreset::Cpu {
@@ -190,5 +190,7 @@ pub fn parse_preset(preset: RingPreset, s: Sender<RunnerResult>) -> Runner<()> {
             update_interval,
             cmd,
         } => custom(s, update_interval, cmd),
-    }
+        RingPreset::Ipc { .. } => return None,
+    };
+    Some(runner)
 }
@@ -6,7 +6,7 @@ use std::{rc::Rc, time::Duration};
 use cairo::ImageSurface;
 use calloop::channel::Sender;
 use chrono::{Local, Utc};
-use config::def::shared::KeyEventMap;
+use config::def::shared::{KeyBinding, KeyEventMap};
 use draw::TextDrawer;
 use interval_task::runner::Runner;
 
@@ -58,8 +58,8 @@ fn custom_preset(s: Sender<String>, time: u64, cmd: String) -> Runner<()> {
     )
 }
 
-fn match_preset(preset: TextPreset, s: Sender<String>) -> Runner<()> {
-    match preset {
+fn match_preset(preset: TextPreset, s: Sender<String>) -> Option<Runner<()>> {
+    let runner = match preset {
         TextPreset::Time {
             format,
             time_zone,
@@ -69,13 +69,17 @@ fn match_preset(preset: TextPreset, s: Sender<String>) -> Runner<()> {
             update_interval,
             cmd,
         } => custom_preset(s, update_interval, cmd),
-    }
+        TextPreset::Ipc { .. } => return None,
+    };
+    Some(runner)
 }
 
 #[derive(Debug)]
 pub struct TextCtx {
     #[allow(dead_code)]
-    runner: Runner<()>,
+    runner: Option<Runner<()>>,
+    // only for ipc preset
+    ipc_signal: Option<Sender<String>>,
     text: Rc<UnsafeCell<String>>,
     drawer: TextDrawer,
     event_map: KeyEventMap,
@@ -87,17 +91,30 @@ impl BoxedWidget for TextCtx {
         self.drawer.draw_text(text)
     }
     fn on_mouse_event(&mut self, e: crate::mouse_state::MouseEvent) -> bool {
-        if let crate::mouse_state::MouseEvent::Release(_, k) = e {
-            self.event_map.call(k);
-        };
+        e.call_event_map(&mut self.event_map);
         false
     }
+    fn on_ipc_trigger(&mut self, key: KeyBinding) -> Result<(), String> {
+        self.event_map.trigger(key)
+    }
+    fn on_ipc_push(&mut self, value: &str, _: Option<&str>) -> Result<(), String> {
+        let Some(signal) = self.ipc_signal.as_ref() else {
+            return Err("only text with `ipc` preset accepts pushed content".to_string());
+        };
+        signal
+            .send(value.to_string())
+            .map_err(|e| format!("Redraw signal error: {e}"))
+    }
 }
 
 pub fn init_text(box_temp_ctx: &mut BoxTemporaryCtx, conf: TextConfig) -> impl BoxedWidget {
     let drawer = TextDrawer::new(&conf);
 
-    let text = Rc::new(UnsafeCell::new(String::default()));
+    let initial_text = match &conf.preset {
+        TextPreset::Ipc { initial_text } => initial_text.clone(),
+        _ => String::default(),
+    };
+    let text = Rc::new(UnsafeCell::new(initial_text));
     let text_weak = Rc::downgrade(&text);
     let redraw_signal = box_temp_ctx.make_redraw_channel(move |_, msg| {
         let Some(text) = text_weak.upgrade() else {
@@ -106,11 +123,15 @@ pub fn init_text(box_temp_ctx: &mut BoxTemporaryCtx, conf: TextConfig) -> impl B
         unsafe { *text.get().as_mut().unwrap() = msg };
     });
 
+    let ipc_signal = matches!(conf.preset, TextPreset::Ipc { .. }).then(|| redraw_signal.clone());
     let mut runner = match_preset(conf.preset, redraw_signal);
-    runner.start().unwrap();
+    if let Some(runner) = runner.as_mut() {
+        runner.start().unwrap();
+    }
 
     TextCtx {
         runner,
+        ipc_signal,
         text,
         drawer,
         event_map: conf.event_map,
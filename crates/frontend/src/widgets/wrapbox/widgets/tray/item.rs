@@ -293,7 +293,7 @@ impl TrayState {
                     return false;
                 };
 
-                if key == BTN_RIGHT {
+                if key.code == BTN_RIGHT {
                     // toggle state
                     let menuitem_id = match hovering {
                         HoveringItem::TrayIcon => {
@@ -315,7 +315,7 @@ impl TrayState {
                         redraw = true;
                         self.menuitem_about_to_show(id);
                     }
-                } else if key == BTN_LEFT {
+                } else if key.code == BTN_LEFT {
                     match hovering {
                         HoveringItem::TrayIcon => self.tray_clicked_req(),
                         HoveringItem::MenuItem(id) => self.menu_item_clicked_req(id),
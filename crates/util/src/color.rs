@@ -113,6 +113,31 @@ pub fn color_mix(one: Color, two: Color) -> Color {
     )
 }
 
+/// `#rrggbbaa`, can be parsed back by `parse_color`.
+pub fn color_to_hex(color: Color) -> String {
+    format!(
+        "#{:02x}{:02x}{:02x}{:02x}",
+        color.r(),
+        color.g(),
+        color.b(),
+        color.a()
+    )
+}
+
+/// replace the alpha channel, `alpha` is between 0 and 1.
+pub fn color_with_alpha(color: Color, alpha: f64) -> Color {
+    let a = (alpha.clamp(0., 1.) * 255.).round() as u8;
+    Color::rgba(color.r(), color.g(), color.b(), a)
+}
+
+/// add `amount` to the hsl lightness, negative to darken.
+pub fn color_lighten(color: Color, amount: f64) -> Color {
+    let [h, s, l] = rgb_to_hsl(color.r(), color.g(), color.b());
+    let l = (l + amount as f32).clamp(0., 1.);
+    let [r, g, b] = hsl_to_rgb(h, s, l);
+    Color::rgba(r, g, b, color.a())
+}
+
 fn parse_color_inner(s: &str) -> Result<[u8; 4], ParseColorError> {
     let s = s.trim().to_lowercase();
 
@@ -246,6 +271,31 @@ fn hsl_to_rgb(h: f32, s: f32, l: f32) -> [u8; 3] {
     ]
 }
 
+fn rgb_to_hsl(r: u8, g: u8, b: u8) -> [f32; 3] {
+    let r = r as f32 / 255.0;
+    let g = g as f32 / 255.0;
+    let b = b as f32 / 255.0;
+
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let l = (max + min) / 2.0;
+    let d = max - min;
+    if d == 0.0 {
+        return [0.0, 0.0, l];
+    }
+
+    let s = d / (1.0 - (2.0 * l - 1.0).abs());
+    let h = if max == r {
+        60.0 * ((g - b) / d).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / d + 2.0)
+    } else {
+        60.0 * ((r - g) / d + 4.0)
+    };
+
+    [h, s, l]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -286,6 +336,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_color_ops() {
+        let c = parse_color("#7b98ff").unwrap();
+        assert_eq!(color_to_hex(c), "#7b98ffff");
+        assert_eq!(color_with_alpha(c, 0.5).a(), 128);
+
+        let red = Color::rgb(255, 0, 0);
+        assert_eq!(color_to_hex(color_lighten(red, 0.1)), "#ff3333ff");
+        assert_eq!(color_to_hex(color_lighten(red, -0.1)), "#cc0000ff");
+        assert_eq!(color_lighten(COLOR_WHITE, 0.1), COLOR_WHITE);
+    }
+
     // #[test]
     // fn test_named_color() {
     //     assert_eq!(parse_color("red").unwrap(), [255, 0, 0, 255]);
@@ -39,6 +39,8 @@ pub enum TemplateContent {
 #[derive(Debug, Clone)]
 pub struct Template {
     pub contents: Vec<TemplateContent>,
+    // the string it was created from, for writing the config back
+    source: String,
 }
 impl Template {
     pub fn create_from_str(raw: &str, processers: TemplateProcesser) -> Result<Self, String> {
@@ -86,7 +88,14 @@ impl Template {
             ));
         };
 
-        Ok(Self { contents })
+        Ok(Self {
+            contents,
+            source: raw.to_string(),
+        })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
     }
 
     pub fn parse(&self, mut cb: impl FnMut(&dyn TemplateArgParser) -> String) -> String {
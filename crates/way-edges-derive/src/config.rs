@@ -0,0 +1,544 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Attribute, Data, DeriveInput, Expr, Fields, Ident, Path, Type};
+
+enum Kind {
+    // `argument` of the child, a `knus::DecodeScalar`
+    Scalar,
+    // bare node is `true`, or `name false`
+    Bool,
+    Color,
+    // the child itself, a `knus::Decode`
+    Node,
+    // children of the widget node itself, like serde's flatten
+    Flatten,
+    // every child with the name, into a `Vec`
+    Children(String),
+    // `fn(&str) -> Result<FieldType, E>` on the argument
+    DecodeWith(Path),
+}
+
+enum FieldDefault {
+    None,
+    Trait,
+    Expr(Expr),
+}
+
+struct ConfigField {
+    ident: Ident,
+    ty: Type,
+    name: String,
+    kind: Kind,
+    default: FieldDefault,
+    // `#[serde]`, `#[schemars]` and doc comments of the field, passed to the serde struct
+    forward: Vec<Attribute>,
+}
+
+fn kebab_case(s: &str) -> String {
+    s.replace('_', "-")
+}
+
+fn kebab_case_variant(s: &str) -> String {
+    let mut out = String::new();
+    for (i, c) in s.chars().enumerate() {
+        if c.is_uppercase() && i != 0 {
+            out.push('-');
+        }
+        out.extend(c.to_lowercase());
+    }
+    out
+}
+
+fn option_inner(ty: &Type) -> Option<&Type> {
+    generic_inner(ty, "Option")
+}
+
+// `T` of `Option<T>` or `Vec<T>`
+fn generic_inner<'a>(ty: &'a Type, outer: &str) -> Option<&'a Type> {
+    let Type::Path(p) = ty else {
+        return None;
+    };
+    let seg = p.path.segments.last()?;
+    if seg.ident != outer {
+        return None;
+    }
+    let syn::PathArguments::AngleBracketed(args) = &seg.arguments else {
+        return None;
+    };
+    match args.args.first()? {
+        syn::GenericArgument::Type(t) => Some(t),
+        _ => None,
+    }
+}
+
+fn is_bool(ty: &Type) -> bool {
+    matches!(ty, Type::Path(p) if p.path.is_ident("bool"))
+}
+
+fn forwarded(attrs: &[Attribute]) -> Vec<Attribute> {
+    attrs
+        .iter()
+        .filter(|a| {
+            ["serde", "schemars", "doc"]
+                .iter()
+                .any(|n| a.path().is_ident(n))
+        })
+        .cloned()
+        .collect()
+}
+
+fn parse_field(field: &syn::Field) -> syn::Result<ConfigField> {
+    let ident = field.ident.clone().unwrap();
+    let ty = field.ty.clone();
+    let mut kind = None;
+    let mut default = FieldDefault::None;
+
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident("config")) {
+        attr.parse_nested_meta(|meta| {
+            let mut set_kind = |k| {
+                if kind.replace(k).is_some() {
+                    return Err(
+                        meta.error("only one of color, node, flatten, children and decode_with")
+                    );
+                }
+                Ok(())
+            };
+            if meta.path.is_ident("default") {
+                default = match meta.value() {
+                    Ok(v) => FieldDefault::Expr(v.parse()?),
+                    Err(_) => FieldDefault::Trait,
+                };
+                Ok(())
+            } else if meta.path.is_ident("color") {
+                set_kind(Kind::Color)
+            } else if meta.path.is_ident("node") {
+                set_kind(Kind::Node)
+            } else if meta.path.is_ident("flatten") {
+                set_kind(Kind::Flatten)
+            } else if meta.path.is_ident("children") {
+                let name = meta.value()?.parse::<syn::LitStr>()?;
+                set_kind(Kind::Children(name.value()))
+            } else if meta.path.is_ident("decode_with") {
+                let path = meta.value()?.parse()?;
+                set_kind(Kind::DecodeWith(path))
+            } else {
+                Err(meta.error("unknown config attribute"))
+            }
+        })?;
+    }
+
+    let inner = option_inner(&ty).unwrap_or(&ty);
+    let kind = kind.unwrap_or(if is_bool(inner) {
+        Kind::Bool
+    } else {
+        Kind::Scalar
+    });
+
+    let name = match &kind {
+        Kind::Children(name) => name.clone(),
+        _ => kebab_case(&ident.to_string()),
+    };
+    Ok(ConfigField {
+        name,
+        ident,
+        ty,
+        kind,
+        default,
+        forward: forwarded(&field.attrs),
+    })
+}
+
+impl ConfigField {
+    fn var(&self) -> Ident {
+        format_ident!("v_{}", self.ident)
+    }
+
+    // decoding one child named after the field, into the field type
+    fn decode_child(&self) -> TokenStream {
+        let inner = option_inner(&self.ty);
+        let value = match &self.kind {
+            Kind::Scalar => quote! { crate::def::util::argv_v(child, ctx)? },
+            Kind::Bool => quote! {
+                match child.arguments.first() {
+                    None => true,
+                    Some(_) => crate::def::util::argv_v(child, ctx)?,
+                }
+            },
+            // empty string for no color
+            Kind::Color if inner.is_some() => {
+                return quote! {
+                    crate::def::util::ToKdlError::to_kdl_error(
+                        crate::def::util::parse_optional_color(&crate::def::util::argv_str(child, ctx)?),
+                        child,
+                    )?
+                };
+            }
+            Kind::Color => quote! {
+                crate::def::util::ToKdlError::to_kdl_error(
+                    ::util::color::parse_color(&crate::def::util::argv_str(child, ctx)?),
+                    child,
+                )?
+            },
+            Kind::Node => {
+                let ty = inner.unwrap_or(&self.ty);
+                quote! { <#ty as ::knus::Decode<S>>::decode_node(child, ctx)? }
+            }
+            Kind::DecodeWith(path) => {
+                return quote! {
+                    crate::def::util::ToKdlError::to_kdl_error(
+                        #path(&crate::def::util::argv_str(child, ctx)?),
+                        child,
+                    )?
+                };
+            }
+            Kind::Flatten | Kind::Children(_) => unreachable!(),
+        };
+        if inner.is_some() {
+            quote! { Some(#value) }
+        } else {
+            value
+        }
+    }
+
+    fn arm(&self) -> TokenStream {
+        let name = &self.name;
+        let var = self.var();
+        if let Kind::Children(_) = &self.kind {
+            let ty = generic_inner(&self.ty, "Vec").unwrap_or(&self.ty);
+            return quote! {
+                #name => #var
+                    .get_or_insert_with(::std::vec::Vec::new)
+                    .push(<#ty as ::knus::Decode<S>>::decode_node(child, ctx)?),
+            };
+        }
+        let decode = self.decode_child();
+        quote! { #name => #var = Some(#decode), }
+    }
+
+    fn finish(&self) -> TokenStream {
+        let var = self.var();
+        let name = &self.name;
+        match &self.default {
+            FieldDefault::Expr(e) => quote! { #var.unwrap_or_else(|| #e) },
+            FieldDefault::Trait => quote! { #var.unwrap_or_default() },
+            FieldDefault::None if self.implicit_default() => quote! { #var.unwrap_or_default() },
+            FieldDefault::None => quote! {
+                #var.ok_or_else(|| ::knus::errors::DecodeError::missing(
+                    node,
+                    concat!("child node `", #name, "` is required"),
+                ))?
+            },
+        }
+    }
+
+    // missing options are `None`, missing flags `false` and missing children empty, as with serde
+    fn implicit_default(&self) -> bool {
+        option_inner(&self.ty).is_some() || matches!(self.kind, Kind::Bool | Kind::Children(_))
+    }
+
+    fn default_fn(&self) -> Ident {
+        format_ident!("__config_default_{}", self.ident)
+    }
+
+    fn serde_field(&self) -> TokenStream {
+        let ident = &self.ident;
+        let ty = &self.ty;
+        let forward = &self.forward;
+        let optional = option_inner(ty).is_some();
+
+        let default = match &self.default {
+            FieldDefault::Expr(_) => {
+                let f = self.default_fn().to_string();
+                quote! { #[serde(default = #f)] }
+            }
+            FieldDefault::Trait => quote! { #[serde(default)] },
+            FieldDefault::None if self.implicit_default() => quote! { #[serde(default)] },
+            FieldDefault::None => quote! {},
+        };
+        let kind = match &self.kind {
+            Kind::Flatten => quote! { #[serde(flatten)] },
+            Kind::Color if optional => quote! {
+                #[serde(deserialize_with = "crate::def::shared::option_color_translate")]
+                #[serde(serialize_with = "crate::def::shared::serialize_optional_color")]
+                #[schemars(schema_with = "crate::def::shared::schema_optional_color")]
+            },
+            Kind::Color => quote! {
+                #[serde(deserialize_with = "crate::def::shared::color_translate")]
+                #[serde(serialize_with = "crate::def::shared::serialize_color")]
+                #[schemars(schema_with = "crate::def::shared::schema_color")]
+            },
+            _ => quote! {},
+        };
+        // `None` is what a missing field gives
+        let skip = match &self.default {
+            FieldDefault::None if optional => {
+                quote! { #[serde(skip_serializing_if = "Option::is_none")] }
+            }
+            _ => quote! {},
+        };
+        quote! {
+            #default
+            #kind
+            #skip
+            #(#forward)*
+            #ident: #ty
+        }
+    }
+}
+
+pub fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    match &input.data {
+        Data::Struct(s) => expand_struct(&input, &s.fields),
+        Data::Enum(e) => expand_enum(&input, e),
+        Data::Union(_) => Err(syn::Error::new_spanned(
+            &input.ident,
+            "Config can't be derived for unions",
+        )),
+    }
+}
+
+fn expand_struct(input: &DeriveInput, fields: &Fields) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let name_str = name.to_string();
+    let Fields::Named(fields) = fields else {
+        return Err(syn::Error::new_spanned(
+            name,
+            "Config needs a struct with named fields",
+        ));
+    };
+    let fields = fields
+        .named
+        .iter()
+        .map(parse_field)
+        .collect::<syn::Result<Vec<_>>>()?;
+    let (flatten, own): (Vec<_>, Vec<_>) =
+        fields.iter().partition(|f| matches!(f.kind, Kind::Flatten));
+
+    // kdl
+    let own_names = own.iter().map(|f| &f.name);
+    let flatten_types = flatten.iter().map(|f| &f.ty).collect::<Vec<_>>();
+    let flatten_decode = flatten.iter().map(|f| {
+        let var = f.var();
+        let ty = &f.ty;
+        quote! { let #var = <#ty>::decode_known(node, ctx)?; }
+    });
+    let own_vars = own.iter().map(|f| f.var()).collect::<Vec<_>>();
+    let own_types = own.iter().map(|f| &f.ty);
+    let own_arms = own.iter().map(|f| f.arm());
+    let construct = fields.iter().map(|f| {
+        let ident = &f.ident;
+        let value = match f.kind {
+            Kind::Flatten => {
+                let var = f.var();
+                quote! { #var }
+            }
+            _ => f.finish(),
+        };
+        quote! { #ident: #value }
+    });
+
+    // serde
+    let struct_attrs = forwarded(&input.attrs);
+    let serde_fields = fields.iter().map(ConfigField::serde_field);
+    let default_fns = fields.iter().filter_map(|f| {
+        let FieldDefault::Expr(e) = &f.default else {
+            return None;
+        };
+        let ident = f.default_fn();
+        let ty = &f.ty;
+        Some(quote! { fn #ident() -> #ty { #e } })
+    });
+    let idents = fields.iter().map(|f| &f.ident).collect::<Vec<_>>();
+
+    Ok(quote! {
+        impl #name {
+            /// children of the kdl node, including the flattened ones.
+            pub const KDL_CHILDREN: &[&str] = {
+                const LISTS: &[&[&str]] = &[#(<#flatten_types>::KDL_CHILDREN,)* &[#(#own_names),*]];
+                const NAMES: [&str; crate::def::util::names_len(LISTS)] =
+                    crate::def::util::concat_names(LISTS);
+                &NAMES
+            };
+        }
+
+        impl #name {
+            /// decode the children this config knows, for nodes shared with other configs,
+            /// the caller reports the rest.
+            pub fn decode_known<S: ::knus::traits::ErrorSpan>(
+                node: &::knus::ast::SpannedNode<S>,
+                ctx: &mut ::knus::decode::Context<S>,
+            ) -> Result<Self, ::knus::errors::DecodeError<S>> {
+                #(#flatten_decode)*
+                #(let mut #own_vars: Option<#own_types> = None;)*
+                for child in node.children() {
+                    match child.node_name.as_ref() {
+                        #(#own_arms)*
+                        _ => {}
+                    }
+                }
+                Ok(Self { #(#construct),* })
+            }
+        }
+
+        impl<S: ::knus::traits::ErrorSpan> ::knus::Decode<S> for #name {
+            fn decode_node(
+                node: &::knus::ast::SpannedNode<S>,
+                ctx: &mut ::knus::decode::Context<S>,
+            ) -> Result<Self, ::knus::errors::DecodeError<S>> {
+                let conf = Self::decode_known(node, ctx)?;
+                crate::def::unknown::check_children(node, &[Self::KDL_CHILDREN], ctx);
+                Ok(conf)
+            }
+        }
+
+        const _: () = {
+            #[derive(::serde::Deserialize, ::serde::Serialize, ::schemars::JsonSchema)]
+            #[serde(rename_all = "kebab-case")]
+            #[schemars(rename = #name_str)]
+            #(#struct_attrs)*
+            struct Raw {
+                #(#serde_fields,)*
+            }
+            #(#default_fns)*
+
+            impl<'de> ::serde::Deserialize<'de> for #name {
+                fn deserialize<D: ::serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+                    let raw = <Raw as ::serde::Deserialize>::deserialize(d)?;
+                    Ok(Self { #(#idents: raw.#idents),* })
+                }
+            }
+            impl ::serde::Serialize for #name {
+                fn serialize<Ser: ::serde::Serializer>(&self, s: Ser) -> Result<Ser::Ok, Ser::Error> {
+                    let raw = Raw { #(#idents: ::std::clone::Clone::clone(&self.#idents)),* };
+                    ::serde::Serialize::serialize(&raw, s)
+                }
+            }
+            impl ::schemars::JsonSchema for #name {
+                fn schema_name() -> ::std::borrow::Cow<'static, str> {
+                    #name_str.into()
+                }
+                fn json_schema(generator: &mut ::schemars::SchemaGenerator) -> ::schemars::Schema {
+                    <Raw as ::schemars::JsonSchema>::json_schema(generator)
+                }
+            }
+        };
+    })
+}
+
+// `preset "name" { ... }` in kdl, `{"type": "name", ...}` or `"name"` in json
+fn expand_enum(input: &DeriveInput, data: &syn::DataEnum) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let name_str = name.to_string();
+
+    let mut arms = vec![];
+    let mut raw_variants = vec![];
+    let mut from_raw = vec![];
+    let mut to_raw = vec![];
+    let mut names = vec![];
+    for v in data.variants.iter() {
+        let ident = &v.ident;
+        let tag = kebab_case_variant(&ident.to_string());
+        let forward = forwarded(&v.attrs);
+        match &v.fields {
+            Fields::Unit => {
+                // no fields, every child is unknown
+                arms.push(quote! {
+                    #tag => {
+                        crate::def::unknown::check_children(node, &[], ctx);
+                        Ok(Self::#ident)
+                    }
+                });
+                raw_variants.push(quote! { #(#forward)* #ident });
+                from_raw.push(quote! { Raw::#ident => Self::#ident });
+                // just the type, as in `preset "hyprland"`
+                to_raw.push(quote! { Self::#ident => s.serialize_str(#tag) });
+            }
+            Fields::Unnamed(f) if f.unnamed.len() == 1 => {
+                let ty = &f.unnamed[0].ty;
+                arms.push(quote! {
+                    #tag => Ok(Self::#ident(<#ty as ::knus::Decode<S>>::decode_node(node, ctx)?)),
+                });
+                raw_variants.push(quote! { #(#forward)* #ident(#ty) });
+                from_raw.push(quote! { Raw::#ident(c) => Self::#ident(c) });
+                to_raw.push(quote! {
+                    Self::#ident(c) => ::serde::Serialize::serialize(&Raw::#ident(c.clone()), s)
+                });
+            }
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    v,
+                    "Config variants are units or hold one config struct",
+                ))
+            }
+        }
+        names.push(tag);
+    }
+    let expected = names.join(", ");
+    let enum_attrs = forwarded(&input.attrs);
+
+    Ok(quote! {
+        impl<S: ::knus::traits::ErrorSpan> ::knus::Decode<S> for #name {
+            fn decode_node(
+                node: &::knus::ast::SpannedNode<S>,
+                ctx: &mut ::knus::decode::Context<S>,
+            ) -> Result<Self, ::knus::errors::DecodeError<S>> {
+                match crate::def::util::argv_str(node, ctx)?.as_str() {
+                    #(#arms)*
+                    other => Err(::knus::errors::DecodeError::unexpected(
+                        node,
+                        "type",
+                        format!("unknown type `{other}`, expected one of: {}", #expected),
+                    )),
+                }
+            }
+        }
+
+        const _: () = {
+            #[derive(::serde::Deserialize, ::serde::Serialize, ::schemars::JsonSchema)]
+            #[serde(rename_all = "kebab-case", tag = "type")]
+            #[schemars(rename = #name_str)]
+            #(#enum_attrs)*
+            enum Raw {
+                #(#raw_variants,)*
+            }
+
+            impl<'de> ::serde::Deserialize<'de> for #name {
+                fn deserialize<D: ::serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+                    let mut value = <::serde_jsonrc::Value as ::serde::Deserialize>::deserialize(d)?;
+                    // only the type, the rest is default
+                    if let ::serde_jsonrc::Value::String(s) = value {
+                        let mut map = ::serde_jsonrc::Map::new();
+                        map.insert("type".to_string(), ::serde_jsonrc::Value::String(s));
+                        value = ::serde_jsonrc::Value::Object(map);
+                    }
+                    let raw = <Raw as ::serde::Deserialize>::deserialize(value)
+                        .map_err(<D::Error as ::serde::de::Error>::custom)?;
+                    Ok(match raw {
+                        #(#from_raw,)*
+                    })
+                }
+            }
+            impl ::serde::Serialize for #name {
+                fn serialize<Ser: ::serde::Serializer>(&self, s: Ser) -> Result<Ser::Ok, Ser::Error> {
+                    match self {
+                        #(#to_raw,)*
+                    }
+                }
+            }
+            impl ::schemars::JsonSchema for #name {
+                fn schema_name() -> ::std::borrow::Cow<'static, str> {
+                    #name_str.into()
+                }
+                fn json_schema(generator: &mut ::schemars::SchemaGenerator) -> ::schemars::Schema {
+                    let tagged = <Raw as ::schemars::JsonSchema>::json_schema(generator);
+                    ::schemars::json_schema!({
+                        "anyOf": [
+                            { "type": "string", "enum": [#(#names),*] },
+                            tagged
+                        ]
+                    })
+                }
+            }
+        };
+    })
+}
@@ -1,3 +1,5 @@
+mod config;
+
 use proc_macro::{self, TokenStream};
 use quote::{format_ident, quote};
 use syn::{
@@ -20,6 +22,24 @@ pub fn derive_size(input: TokenStream) -> TokenStream {
     .into()
 }
 
+/// kdl decoder, serde and json schema of a config struct or tagged enum from one definition.
+/// serializing needs `Clone`, `None` of options without a default is left out.
+///
+/// fields are kdl children named in kebab-case, taking one argument unless marked with
+/// `#[config(node)]` (decoded from the child node), `#[config(children = "name")]` (every child
+/// with the name into a `Vec`) or `#[config(flatten)]`.
+/// `#[config(default)]`, `#[config(default = expr)]`, `#[config(color)]` and
+/// `#[config(decode_with = fn)]` apply to both formats, `#[serde]` and `#[schemars]` are kept for json.
+/// unknown children are reported by `Decode`, `decode_known` leaves them to nodes shared with
+/// other configs.
+#[proc_macro_derive(Config, attributes(config, serde, schemars))]
+pub fn derive_config(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    config::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
 #[proc_macro_attribute]
 pub fn wrap_rc(attr: TokenStream, item: TokenStream) -> TokenStream {
     let args = parse_macro_input!(attr with Punctuated<Meta, Token![,]>::parse_terminated);
@@ -21,6 +21,10 @@ pub struct Cli {
     #[arg(short = 'c', long)]
     pub config_path: Option<String>,
 
+    /// send a desktop notification with `notify-send` when reloading the config fails.
+    #[arg(short = 'n', long)]
+    pub notify_errors: bool,
+
     #[arg(short = 'i', long)]
     pub ipc_namespace: Option<String>,
 
@@ -50,19 +54,133 @@ pub enum Command {
     #[command(name = "schema")]
     Schema,
 
+    /// load and validate the configuration without starting the daemon, exits non-zero on any problem.
+    #[command(name = "check")]
+    Check {
+        /// config file to check, defaults to the one the daemon loads.
+        path: Option<String>,
+
+        /// unknown nodes are errors instead of warnings.
+        #[arg(long)]
+        strict: bool,
+    },
+
+    /// convert or format configuration files without starting the daemon.
+    #[command(name = "config")]
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+
     /// (deprecated) run daemon. There can only be one daemon at a time.
     #[command(name = "daemon", alias = "d")]
     Daemon,
 
-    /// toggle pin of a widget under certain group.
-    /// format: <group_name>:<widget_name>
+    /// toggle pin of widgets matching the namespace pattern.
     #[command(name = "togglepin")]
     TogglePin {
-        /// format: <group_name>:<widget_name>
+        /// namespace pattern: `group:name`, `group` for the whole group, `audio:*`, `*:left`, `re:<regex>`
+        #[clap(add = ArgValueCompleter::new(complete_widget_name))]
+        namespace: String,
+    },
+
+    /// show a widget until mouse leaves or `hide` is called.
+    #[command(name = "show")]
+    Show {
+        /// namespace pattern: `group:name`, `group` for the whole group, `audio:*`, `*:left`, `re:<regex>`
+        #[clap(add = ArgValueCompleter::new(complete_widget_name))]
+        namespace: String,
+    },
+
+    /// hide a widget, pinned widget will stay.
+    #[command(name = "hide")]
+    Hide {
+        /// namespace pattern: `group:name`, `group` for the whole group, `audio:*`, `*:left`, `re:<regex>`
+        #[clap(add = ArgValueCompleter::new(complete_widget_name))]
+        namespace: String,
+    },
+
+    /// remove a widget from the screen and stop its backends until `enable`, survives `reload`.
+    #[command(name = "disable")]
+    Disable {
+        /// namespace pattern: `group:name`, `group` for the whole group, `audio:*`, `*:left`, `re:<regex>`
+        #[clap(add = ArgValueCompleter::new(complete_widget_name))]
+        namespace: String,
+    },
+
+    /// bring back a disabled widget from the loaded configuration.
+    #[command(name = "enable")]
+    Enable {
+        /// namespace pattern: `group:name`, `group` for the whole group, `audio:*`, `*:left`, `re:<regex>`
+        #[clap(add = ArgValueCompleter::new(complete_widget_name))]
+        namespace: String,
+    },
+
+    /// pop up a widget and hide it after a duration, like an OSD.
+    #[command(name = "pop")]
+    Pop {
+        /// namespace pattern: `group:name`, `group` for the whole group, `audio:*`, `*:left`, `re:<regex>`
+        #[clap(add = ArgValueCompleter::new(complete_widget_name))]
+        namespace: String,
+
+        /// milliseconds to keep the widget shown.
+        #[arg(long, default_value_t = ipc::DEFAULT_POP_DURATION)]
+        duration: u64,
+    },
+
+    /// set the value of a slider with `ipc` preset.
+    #[command(name = "set")]
+    Set {
+        /// namespace pattern: `group:name`, `group` for the whole group, `audio:*`, `*:left`, `re:<regex>`
         #[clap(add = ArgValueCompleter::new(complete_widget_name))]
         namespace: String,
+
+        /// value between 0 and 1.
+        value: f64,
+    },
+
+    /// push content to a wrap-box item with `ipc` preset.
+    #[command(name = "push")]
+    Push {
+        /// format: <namespace pattern>/<item-id>
+        target: String,
+
+        /// text for text item, number between 0 and 1 for ring item.
+        value: String,
+
+        /// text of ring item, available as `{preset}` in prefix and suffix.
+        #[arg(long)]
+        label: Option<String>,
     },
 
+    /// run the command bound in the event map of a widget, as if the key was released on it.
+    #[command(name = "trigger")]
+    Trigger {
+        /// format: <namespace pattern> or <namespace pattern>/<item-id> for wrap-box items
+        #[clap(add = ArgValueCompleter::new(complete_widget_name))]
+        target: String,
+
+        /// `mouse-left` like names or `kc-<number>`, same as the event map.
+        key: String,
+    },
+
+    /// list widgets of the running daemon.
+    #[command(name = "list", alias = "status")]
+    List {
+        /// print raw json instead of a table.
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// print state change events of the daemon as json lines until it exits.
+    #[command(name = "subscribe")]
+    Subscribe,
+
+    /// read commands from stdin, one per line like `togglepin audio`, send them over one connection.
+    /// arguments are separated by whitespace.
+    #[command(name = "batch")]
+    Batch,
+
     /// reload widget configuration
     #[command(name = "reload")]
     Reload,
@@ -71,23 +189,101 @@ pub enum Command {
     #[command(name = "quit", alias = "q")]
     Exit,
 }
+
+#[derive(Subcommand, Debug, PartialEq, Clone)]
+pub enum ConfigCommand {
+    /// load a config file of any supported format and print it as kdl or jsonc.
+    #[command(name = "convert")]
+    Convert {
+        path: String,
+
+        /// `kdl` or `jsonc`, defaults to the extension of `--output`, or the other format of `path`.
+        #[arg(long)]
+        to: Option<config::ConfigFormat>,
+
+        /// write to this file instead of the stdout.
+        #[arg(short = 'o', long)]
+        output: Option<String>,
+    },
+
+    /// normalize ordering and indentation of a config file, keeping its format.
+    #[command(name = "fmt")]
+    Fmt {
+        /// config file to format, defaults to the one the daemon loads.
+        path: Option<String>,
+
+        /// write the result back to the file instead of the stdout,
+        /// refused if the file has comments or `theme`, `template`, `defaults` or `strict`.
+        #[arg(short = 'w', long)]
+        write: bool,
+    },
+}
+
 impl Command {
-    pub fn send_ipc(&self) {
+    pub fn send_ipc(&self) -> Result<ipc::IPCResponse, String> {
+        ipc::send_command(self.command_body()?)
+    }
+
+    pub fn command_body(&self) -> Result<ipc::CommandBody, String> {
         let (command, args) = match self {
             Self::Exit => (ipc::IPC_COMMAND_QUIT, vec![]),
             Self::TogglePin { namespace } => {
                 (ipc::IPC_COMMAND_TOGGLE_PIN, vec![namespace.to_string()])
             }
+            Self::Show { namespace } => (ipc::IPC_COMMAND_SHOW, vec![namespace.to_string()]),
+            Self::Hide { namespace } => (ipc::IPC_COMMAND_HIDE, vec![namespace.to_string()]),
+            Self::Disable { namespace } => (ipc::IPC_COMMAND_DISABLE, vec![namespace.to_string()]),
+            Self::Enable { namespace } => (ipc::IPC_COMMAND_ENABLE, vec![namespace.to_string()]),
+            Self::Pop {
+                namespace,
+                duration,
+            } => (
+                ipc::IPC_COMMAND_POP,
+                vec![namespace.to_string(), duration.to_string()],
+            ),
+            Self::Push {
+                target,
+                value,
+                label,
+            } => {
+                let mut args = vec![target.to_string(), value.to_string()];
+                args.extend(label.clone());
+                (ipc::IPC_COMMAND_PUSH, args)
+            }
+            Self::Trigger { target, key } => (
+                ipc::IPC_COMMAND_TRIGGER,
+                vec![target.to_string(), key.to_string()],
+            ),
+            Self::List { .. } => (ipc::IPC_COMMAND_LIST, vec![]),
+            Self::Set { namespace, value } => (
+                ipc::IPC_COMMAND_SET,
+                vec![namespace.to_string(), value.to_string()],
+            ),
             Self::Reload => (ipc::IPC_COMMAND_RELOAD, vec![]),
             _ => {
-                return;
+                return Err(format!("{self:?} is not an ipc command"));
             }
         };
 
-        ipc::send_command(ipc::CommandBody {
-            command: command.to_string(),
-            args,
-        });
+        Ok(ipc::CommandBody::new(command, args))
+    }
+}
+
+/// parse one line of `batch` input.
+pub fn parse_batch_line(line: &str) -> Result<Command, String> {
+    let words = std::iter::once("way-edges").chain(line.split_whitespace());
+    let cli = Cli::try_parse_from(words).map_err(|e| e.to_string())?;
+    match cli.command {
+        Some(
+            Command::Schema
+            | Command::Check { .. }
+            | Command::Config { .. }
+            | Command::Daemon
+            | Command::Subscribe
+            | Command::Batch,
+        )
+        | None => Err(format!("`{line}` can not be used in batch")),
+        Some(cmd) => Ok(cmd),
     }
 }
 
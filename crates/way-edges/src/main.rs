@@ -4,6 +4,7 @@ use frontend::run_app;
 use log::Level;
 use std::env;
 use std::io::Write;
+use std::path::PathBuf;
 
 fn main() {
     // completion script output, and exit
@@ -41,6 +42,15 @@ fn main() {
 
     let cli = args::get_args();
 
+    if let Some(args::Command::Check { path, strict }) = cli.command.as_ref() {
+        config::set_config_path(path.as_deref().or(cli.config_path.as_deref()));
+        std::process::exit(run_check(*strict));
+    }
+
+    if let Some(args::Command::Config { command }) = cli.command.as_ref() {
+        std::process::exit(run_config_command(command, cli.config_path.as_deref()));
+    }
+
     config::set_config_path(cli.config_path.as_deref());
     backend::ipc::set_ipc_namespace(cli.ipc_namespace.as_deref());
 
@@ -53,12 +63,234 @@ fn main() {
                 config::output_json_schema();
                 return;
             }
-            _ => {
-                cmd.send_ipc();
+            args::Command::Subscribe => {
+                let res = backend::ipc::subscribe(|line| {
+                    let mut stdout = std::io::stdout().lock();
+                    let _ = writeln!(stdout, "{line}").and_then(|_| stdout.flush());
+                });
+                if let Err(e) = res {
+                    eprintln!("{e}");
+                    std::process::exit(1);
+                }
                 return;
             }
+            args::Command::Batch => {
+                std::process::exit(run_batch());
+            }
+            _ => {
+                let code = print_ipc_response(cmd, cmd.send_ipc());
+                std::process::exit(code);
+            }
+        }
+    }
+
+    run_app(cli.mouse_debug, cli.notify_errors);
+}
+
+/// validate the config file, returns the exit code.
+fn run_check(strict: bool) -> i32 {
+    let path = config::get_config_path();
+    match config::check_config(path, strict) {
+        Ok(root) => {
+            println!("{}: ok, {} widgets", path.display(), root.widgets.len());
+            0
+        }
+        Err(errors) => {
+            for e in errors.iter() {
+                eprintln!("Error: {e}");
+            }
+            let problems = if errors.len() == 1 {
+                "problem"
+            } else {
+                "problems"
+            };
+            eprintln!("{}: {} {problems} found", path.display(), errors.len());
+            1
+        }
+    }
+}
+
+/// convert or format a config file, returns the exit code.
+fn run_config_command(command: &args::ConfigCommand, config_path: Option<&str>) -> i32 {
+    use config::ConfigFormat;
+
+    let (path, format, output) = match command {
+        args::ConfigCommand::Convert { path, to, output } => {
+            let path = PathBuf::from(path);
+            let output = output.as_ref().map(PathBuf::from);
+            let format = to
+                .or_else(|| output.as_deref().and_then(ConfigFormat::from_path))
+                .unwrap_or(match ConfigFormat::from_path(&path) {
+                    Some(ConfigFormat::Kdl) => ConfigFormat::Jsonc,
+                    _ => ConfigFormat::Kdl,
+                });
+            (path, format, output)
+        }
+        args::ConfigCommand::Fmt { path, write } => {
+            let path = match path {
+                Some(p) => PathBuf::from(p),
+                None => {
+                    config::set_config_path(config_path);
+                    config::get_config_path().to_path_buf()
+                }
+            };
+            let format = ConfigFormat::from_path(&path).unwrap_or(ConfigFormat::Kdl);
+            if *write {
+                match config::fmt_losses(&path) {
+                    Ok(losses) if losses.is_empty() => {}
+                    Ok(losses) => {
+                        eprintln!(
+                            "Error: {} has {}, which `fmt -w` can not write back, run it without `-w` to see the result",
+                            path.display(),
+                            losses.join(", ")
+                        );
+                        return 1;
+                    }
+                    Err(e) => {
+                        eprintln!("Error: {e}");
+                        return 1;
+                    }
+                }
+            }
+            let output = write.then(|| path.clone());
+            (path, format, output)
+        }
+    };
+
+    let res = config::convert_config(&path, format).and_then(|s| match output {
+        Some(output) => std::fs::write(&output, s)
+            .map_err(|e| format!("Fail to write {}: {e}", output.display())),
+        None => {
+            print!("{s}");
+            Ok(())
+        }
+    });
+    match res {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            1
+        }
+    }
+}
+
+/// send every command from stdin over one connection, returns the exit code.
+fn run_batch() -> i32 {
+    let mut client = match backend::ipc::IPCClient::connect() {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("{e}");
+            return 1;
+        }
+    };
+
+    let mut code = 0;
+    for line in std::io::stdin().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => {
+                eprintln!("Fail to read stdin: {e}");
+                return 1;
+            }
+        };
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        let res = args::parse_batch_line(&line).map(|cmd| {
+            let res = cmd.command_body().and_then(|body| client.send(body));
+            print_ipc_response(&cmd, res)
+        });
+        match res {
+            Ok(0) => {}
+            Ok(c) => code = c,
+            Err(e) => {
+                eprintln!("{e}");
+                code = 1;
+            }
+        }
+    }
+    code
+}
+
+/// print the reply of the daemon, returns the exit code.
+fn print_ipc_response(cmd: &args::Command, res: Result<backend::ipc::IPCResponse, String>) -> i32 {
+    let res = match res {
+        Ok(res) => res,
+        Err(e) => {
+            eprintln!("{e}");
+            return 1;
+        }
+    };
+
+    if res.is_ok() {
+        if let Some(msg) = res.message.as_ref() {
+            println!("{msg}");
+        }
+        if let Some(payload) = res.payload {
+            match cmd {
+                args::Command::List { json: false } => print_widget_status(payload),
+                _ => println!("{}", serde_jsonrc::to_string_pretty(&payload).unwrap()),
+            }
         }
+        0
+    } else {
+        eprintln!(
+            "Error: {}",
+            res.message.as_deref().unwrap_or("unknown error")
+        );
+        1
     }
+}
 
-    run_app(cli.mouse_debug);
+fn print_widget_status(payload: serde_jsonrc::Value) {
+    let list: Vec<backend::ipc::WidgetStatus> = match serde_jsonrc::from_value(payload) {
+        Ok(list) => list,
+        Err(e) => {
+            eprintln!("Invalid widget list: {e}");
+            return;
+        }
+    };
+
+    let rows = list
+        .into_iter()
+        .map(|w| {
+            [
+                w.namespace,
+                w.widget_type,
+                w.output.unwrap_or_else(|| "-".to_string()),
+                w.edge,
+                w.pinned.to_string(),
+                format!("{:.2}", w.pop_progress),
+                w.configured.to_string(),
+            ]
+        })
+        .collect::<Vec<_>>();
+    let header = [
+        "NAMESPACE",
+        "TYPE",
+        "OUTPUT",
+        "EDGE",
+        "PINNED",
+        "POP",
+        "CONFIGURED",
+    ]
+    .map(String::from);
+
+    let mut widths = header.clone().map(|h| h.len());
+    rows.iter().for_each(|row| {
+        row.iter()
+            .enumerate()
+            .for_each(|(i, c)| widths[i] = widths[i].max(c.len()))
+    });
+
+    for row in std::iter::once(&header).chain(rows.iter()) {
+        let line = row
+            .iter()
+            .enumerate()
+            .map(|(i, c)| format!("{c:<width$}", width = widths[i]))
+            .collect::<Vec<_>>()
+            .join("  ");
+        println!("{}", line.trim_end());
+    }
 }